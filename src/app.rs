@@ -1,36 +1,169 @@
 mod canvas;
+mod clipboard;
 mod cmd_line;
+mod document;
+mod export;
+mod history;
+mod import;
 mod mode;
+mod register;
+mod scene;
 mod shape;
+mod storage;
+mod swap;
 
-use self::{canvas::CanvasHandler, mode::ModeHandler, shape::Shape};
+use self::{
+    canvas::CanvasHandler,
+    cmd_line::CmdLine,
+    mode::ModeHandler,
+    shape::{style::Style, Shape},
+};
 use crate::util::{Coord, Size};
-use canvas::ShapeIdSet;
+use anyhow::Context;
+use canvas::{Canvas, ShapeIdSet};
 use crossterm::{
-    event, execute,
+    cursor,
+    event::{self, DisableBracketedPaste, EnableBracketedPaste},
+    execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use history::{Change, History};
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
+    widgets::Paragraph,
     Frame, Terminal,
 };
+use register::Registers;
+use std::{
+    fs::File,
+    io::{IsTerminal, Write},
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use storage::Storage;
 
 pub enum AppOp {
     MakeShape(Coord, Box<dyn Shape>),
+    /// Make shapes ordered from back to front.
+    MakeShapes(Vec<(Coord, Box<dyn Shape>)>),
     MoveCanvasCursor(crate::util::Direction),
     SetCanvasCursor(Coord),
     DeleteShapes(ShapeIdSet),
     MoveShapes(ShapeIdSet, crate::util::Direction),
-    QuitApp,
+    /// Write the document to the storage (or the current storage if `None`).
+    WriteDocument(Option<Storage>),
+    /// Write the document like `WriteDocument`, and quit if succeeded.
+    WriteDocumentAndQuit(Option<Storage>),
+    /// Replace the canvas with the document in the storage.
+    /// Unsaved changes are discarded only if the flag is true.
+    EditDocument(Storage, bool),
+    /// Replace the canvas with the current document on disk.
+    ReloadDocument,
+    /// Merge the changes of the current document on disk into the canvas.
+    MergeDocument,
+    /// Export the canvas to the file in the format.
+    ExportCanvas(PathBuf, export::Format),
+    /// Import the file at the cursor.
+    ImportFile(PathBuf, Option<Style>),
+    /// Replace the canvas with the content of the swap file of the document.
+    RecoverSwap,
+    /// Remove the swap file of the document left by another session.
+    DiscardSwap,
+    /// Copy the rendered shapes (or the whole canvas if `None`) to the system clipboard.
+    YankText(Option<ShapeIdSet>),
+    /// Copy the shapes into the register (or the unnamed register if `None`).
+    YankShapes(Option<char>, ShapeIdSet),
+    /// Make a copy of the shapes in the register at the cursor.
+    /// The shapes end at the cursor if the flag is true, and start at it otherwise.
+    PutShapes(Option<char>, bool),
+    /// Undo the last change of the canvas.
+    Undo,
+    /// Redo the last undone change of the canvas.
+    Redo,
+    /// Mark the cursor position with the name.
+    SetMark(char),
+    /// Go back to the position before the last jump.
+    JumpOlder,
+    /// Go forward in the jump list.
+    JumpNewer,
+    /// Show message in command line.
+    ShowNotice(String),
+    /// Quit. Unsaved changes are discarded only if the flag is true.
+    QuitApp(bool),
     Nop,
 }
 
+impl AppOp {
+    /// Return true if the operation changes the shapes on the canvas.
+    fn is_change(&self) -> bool {
+        matches!(
+            self,
+            AppOp::MakeShape(..)
+                | AppOp::MakeShapes(_)
+                | AppOp::DeleteShapes(_)
+                | AppOp::MoveShapes(..)
+                | AppOp::PutShapes(..)
+                | AppOp::ImportFile(..)
+        )
+    }
+}
+
+/// Load the document at the location (see `Storage`) and export it without running the TUI.
+/// The format is given by its name, or guessed from `output` if `None`.
+pub fn render(
+    location: &str,
+    format: Option<&str>,
+    output: Option<&Path>,
+) -> anyhow::Result<String> {
+    let format = match (format, output) {
+        (Some(format), _) => format.parse()?,
+        (None, Some(output)) => export::Format::from_path(output),
+        (None, None) => export::Format::PlainText,
+    };
+    let mut storage: Storage = location.parse()?;
+    let canvas: Canvas = storage.load()?.into_iter().collect();
+    Ok(export::export(&canvas, format))
+}
+
 /// The application
 #[derive(Default)]
 pub struct App {
     canvas_handler: CanvasHandler,
     mode: ModeHandler,
+    /// Storage of the document being edited.
+    storage: Option<Storage>,
+    /// Message shown in command line instead of the status of the mode.
+    notice: Option<String>,
+    /// Swap file written by this app, removed when the document is written or the app quits.
+    swap: Option<PathBuf>,
+    /// True if the canvas has been changed since the swap file was written.
+    swap_is_stale: bool,
+    /// Swap file left by another session, kept until it is recovered or discarded.
+    found_swap: Option<PathBuf>,
+    /// True if the canvas has been changed since the document was loaded or written.
+    changed: bool,
+    /// Shapes of the document when it was loaded or written, used to merge changes on disk.
+    base: Vec<(Coord, String)>,
+    /// Modification time of the document when it was loaded, written or found changed.
+    modified: Option<SystemTime>,
+    /// Changes of the canvas to undo and redo.
+    history: History,
+    /// Shapes yanked to put.
+    registers: Registers,
+}
+
+/// Idle time after which the swap file is written and the document is checked for changes.
+const IDLE_TIME: Duration = Duration::from_secs(1);
+
+/// Notice shown when a command would discard unsaved changes.
+const NOT_WRITTEN: &str = "No write since last change (add ! to override)";
+
+/// Key to compare shapes of different canvases.
+fn shape_key(coord: Coord, shape: &dyn Shape) -> (Coord, String) {
+    (coord, shape.encode())
 }
 
 impl App {
@@ -38,6 +171,275 @@ impl App {
         App::default()
     }
 
+    /// Open the document at the location given by user (see `Storage`).
+    pub fn open(&mut self, location: &str) -> anyhow::Result<()> {
+        self.load(location.parse()?)
+    }
+
+    /// Load the document from the storage.
+    /// If the file does not exist, start a new document which will be written to the storage.
+    fn load(&mut self, mut storage: Storage) -> anyhow::Result<()> {
+        // Stdin was consumed by the first load, so reload what was read.
+        if let (Storage::Pipe { input, .. }, Some(Storage::Pipe { input: read, .. })) =
+            (&mut storage, &self.storage)
+        {
+            input.clone_from(read);
+        }
+        let canvas: Canvas = if matches!(storage, Storage::File(_)) && !storage.exists() {
+            self.notice = Some(format!("\"{}\" [New]", storage));
+            Canvas::default()
+        } else {
+            storage.load()?.into_iter().collect()
+        };
+
+        self.base = canvas
+            .shapes()
+            .map(|(c, s)| shape_key(*c, s.as_ref()))
+            .collect();
+        self.modified = storage.modified();
+        self.canvas_handler.set_canvas(canvas);
+        self.history.clear();
+        self.changed = false;
+        self.remove_swap();
+        self.found_swap = swap::find(&storage);
+        if let Some(swap) = &self.found_swap {
+            self.notice = Some(format!(
+                "Found swap file \"{}\", :recover to recover it or :discard to remove it",
+                swap.display()
+            ));
+        }
+        self.storage = Some(storage);
+        Ok(())
+    }
+
+    /// Write the document to `storage`, or to the current storage if `storage` is `None`.
+    /// The storage of the document is set to `storage` if there is no current storage.
+    fn write(&mut self, storage: Option<Storage>) -> anyhow::Result<Storage> {
+        // Compare the locations, since a storage may keep what it found on load.
+        let location = |s: &Option<Storage>| s.as_ref().map(Storage::to_string);
+        let is_current = storage.is_none()
+            || self.storage.is_none()
+            || location(&storage) == location(&self.storage);
+        let Some(mut storage) = storage.or_else(|| self.storage.clone()) else {
+            anyhow::bail!("No file name");
+        };
+
+        storage.save(self.canvas_handler.canvas())?;
+
+        if is_current {
+            self.remove_swap();
+            self.changed = false;
+            let canvas = self.canvas_handler.canvas();
+            self.base = canvas
+                .shapes()
+                .map(|(c, s)| shape_key(*c, s.as_ref()))
+                .collect();
+            self.modified = storage.modified();
+            self.storage = Some(storage.clone());
+        }
+        Ok(storage)
+    }
+
+    /// Reload the current document.
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let Some(storage) = self.storage.clone() else {
+            anyhow::bail!("No file name");
+        };
+        self.load(storage)
+    }
+
+    /// Merge the changes of the current document on disk since it was loaded or written.
+    /// Shapes removed on disk are removed from the canvas, and shapes added on disk are added
+    /// to the canvas. Return the numbers of removed and added shapes.
+    fn merge(&mut self) -> anyhow::Result<(usize, usize)> {
+        let Some(storage) = &mut self.storage else {
+            anyhow::bail!("No file name");
+        };
+        let disk = storage.load()?;
+        let disk_keys: Vec<_> = disk
+            .iter()
+            .map(|(c, s)| shape_key(*c, s.as_ref()))
+            .collect();
+
+        let removed_keys: Vec<_> = self
+            .base
+            .iter()
+            .filter(|key| !disk_keys.contains(key))
+            .collect();
+        let removed = self
+            .canvas_handler
+            .retain_shapes(|c, s| !removed_keys.contains(&&shape_key(c, s)));
+        let num_removed = removed.len();
+
+        let added: ShapeIdSet = disk
+            .into_iter()
+            .zip(&disk_keys)
+            .filter(|(_, key)| !self.base.contains(key))
+            .map(|((coord, shape), _)| self.canvas_handler.add_shape(coord, shape))
+            .collect();
+        let num_added = added.num_elems() as usize;
+        self.history.record(Change::Batch(vec![
+            Change::Delete(added),
+            Change::Insert(removed),
+        ]));
+
+        self.base = disk_keys;
+        self.modified = storage.modified();
+        if num_removed + num_added > 0 {
+            self.set_changed();
+        }
+        Ok((num_removed, num_added))
+    }
+
+    /// Return true if the current document has been changed on disk since it was checked.
+    fn check_modified(&mut self) -> bool {
+        let Some(modified) = self.storage.as_ref().and_then(Storage::modified) else {
+            return false;
+        };
+        let changed = self.modified != Some(modified);
+        self.modified = Some(modified);
+        changed
+    }
+
+    /// Called when no event has come for `IDLE_TIME`.
+    fn on_idle(&mut self) {
+        if self.swap_is_stale {
+            if let Err(e) = self.write_swap() {
+                self.notice = Some(format!("{:#}", e));
+            }
+        }
+        if self.check_modified() {
+            self.notice = Some(format!(
+                "\"{}\" changed on disk, :e! to reload or :merge to merge",
+                self.storage.as_ref().unwrap()
+            ));
+        }
+    }
+
+    /// Note that the canvas has been changed.
+    fn set_changed(&mut self) {
+        self.changed = true;
+        self.swap_is_stale = true;
+    }
+
+    /// Write the canvas to the swap file of the document.
+    /// A new swap file is made if this app has not written one yet.
+    fn write_swap(&mut self) -> anyhow::Result<()> {
+        self.swap_is_stale = false;
+        let Some(path) = self.own_swap() else {
+            return Ok(());
+        };
+        swap::write(&path, self.canvas_handler.canvas())?;
+        self.swap = Some(path);
+        Ok(())
+    }
+
+    /// Remove the swap file written by this app.
+    fn remove_swap(&mut self) {
+        self.swap_is_stale = false;
+        if let Some(swap) = self.swap.take() {
+            let _ = std::fs::remove_file(swap);
+        }
+    }
+
+    /// Swap file written by this app, or a new one which does not overwrite existing ones.
+    fn own_swap(&self) -> Option<PathBuf> {
+        self.swap
+            .clone()
+            .or_else(|| self.storage.as_ref().and_then(swap::new_path))
+    }
+
+    /// Replace the canvas with the content of the swap file left by another session.
+    /// The swap file is taken over by this app.
+    fn recover(&mut self) -> anyhow::Result<PathBuf> {
+        let Some(path) = self.found_swap.clone() else {
+            anyhow::bail!("No swap file");
+        };
+        let canvas = swap::read(&path)?;
+        self.canvas_handler.set_canvas(canvas);
+        self.history.clear();
+        self.remove_swap();
+        self.found_swap = None;
+        self.swap = Some(path.clone());
+        // The recovered canvas is not written to the document yet.
+        self.changed = true;
+        Ok(path)
+    }
+
+    /// Remove the swap file left by another session.
+    fn discard_swap(&mut self) -> anyhow::Result<PathBuf> {
+        let Some(path) = self.found_swap.clone() else {
+            anyhow::bail!("No swap file");
+        };
+        std::fs::remove_file(&path)
+            .with_context(|| format!("failed to remove swap file {}", path.display()))?;
+        self.found_swap = None;
+        Ok(path)
+    }
+
+    /// Export the canvas to the file in the format.
+    fn export(&self, path: &Path, format: export::Format) -> anyhow::Result<()> {
+        let exported = export::export(self.canvas_handler.canvas(), format);
+        std::fs::write(path, exported)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Import the file at the cursor. Return the number of imported shapes, and the number of
+    /// edges from a node to itself in a graph, which are not drawn.
+    /// A JSON scene (`.json`) is imported as its shapes, a Graphviz DOT graph (`.dot`, `.gv`)
+    /// and a Mermaid flowchart (`.mmd`, `.mermaid`) are laid out, a CSV file (`.csv`) is imported
+    /// as a table in the line style (single if `None`), and other files are imported as plain
+    /// texts.
+    pub fn import(&mut self, path: &Path, style: Option<Style>) -> anyhow::Result<(usize, usize)> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let (shapes, num_self_loops) = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => {
+                let shapes = scene::decode(&text)
+                    .with_context(|| format!("failed to import {}", path.display()))?;
+                (shapes, 0)
+            }
+            Some("dot" | "gv") => {
+                let diagram = import::dot::diagram(&text)
+                    .with_context(|| format!("failed to import {}", path.display()))?;
+                (diagram.shapes, diagram.num_self_loops)
+            }
+            Some("mmd" | "mermaid") => {
+                let diagram = import::mermaid::diagram(&text)
+                    .with_context(|| format!("failed to import {}", path.display()))?;
+                (diagram.shapes, diagram.num_self_loops)
+            }
+            Some("csv") => {
+                let table = import::csv::table(&text, style.unwrap_or(Style::Single))
+                    .with_context(|| format!("failed to import {}", path.display()))?;
+                (
+                    vec![(Coord::default(), Box::new(table) as Box<dyn Shape>)],
+                    0,
+                )
+            }
+            _ => (import::diagram(&text), 0),
+        };
+        let num_shapes = shapes.len();
+
+        let cursor = self.canvas_handler.cursor_coord();
+        let shapes = shapes
+            .into_iter()
+            .map(|(coord, shape)| (cursor + coord, shape))
+            .collect();
+        self.add_shapes(shapes);
+        Ok((num_shapes, num_self_loops))
+    }
+
+    /// Add the shapes ordered from back to front, as a change which can be undone.
+    fn add_shapes(&mut self, shapes: Vec<(Coord, Box<dyn Shape>)>) {
+        let ids = shapes
+            .into_iter()
+            .map(|(coord, shape)| self.canvas_handler.add_shape(coord, shape))
+            .collect();
+        self.history.record(Change::Delete(ids));
+        self.set_changed();
+    }
+
     fn render(&mut self, f: &mut Frame) {
         let canvas_area = Constraint::Length(f.area().height - 1);
         let cmd_line_area = Constraint::Length(1);
@@ -66,46 +468,283 @@ impl App {
         f.render_widget(&mut self.canvas_handler, *canvas_area);
 
         // Render command line
-        let cmd_line = self.mode.get().cmd_line();
-        f.render_widget(cmd_line, *cmd_line_area);
+        match &self.notice {
+            Some(notice) => {
+                let cmd_line = CmdLine::new(Paragraph::new(notice.as_str()));
+                f.render_widget(cmd_line, *cmd_line_area);
+            }
+            None => f.render_widget(self.mode.get().cmd_line(), *cmd_line_area),
+        }
+        if let Some(register) = self.mode.recording() {
+            let recording = Paragraph::new(format!("recording @{}", register))
+                .alignment(ratatui::layout::Alignment::Right);
+            f.render_widget(recording, *cmd_line_area);
+        }
+    }
+
+    /// Copy the rendered shapes (or the whole canvas if `None`) to the system clipboard
+    /// through the terminal, and return the number of copied lines.
+    fn yank(&self, w: &mut impl Write, ids: Option<&ShapeIdSet>) -> std::io::Result<usize> {
+        let canvas = self.canvas_handler.canvas();
+        let text = match ids {
+            Some(ids) => canvas.subset(ids).to_plain_text(),
+            None => canvas.to_plain_text(),
+        };
+        clipboard::copy(w, &text)?;
+        Ok(text.lines().count())
     }
 
     /// Main loop
-    fn main_loop(&mut self, terminal: &mut Terminal<impl Backend>) {
+    fn main_loop(&mut self, terminal: &mut Terminal<impl Backend + Write>) -> std::io::Result<()> {
         use AppOp::*;
         loop {
-            terminal.draw(|f| self.render(f)).unwrap();
-            let event = event::read().unwrap();
-
-            let op = self.mode.process_event(event, &self.canvas_handler);
-
-            match op {
-                QuitApp => break,
-                MakeShape(c, s) => self.canvas_handler.add_shape(c, s),
-                MoveCanvasCursor(d) => self.canvas_handler.move_cursor(d),
-                SetCanvasCursor(c) => self.canvas_handler.set_cursor(c),
-                DeleteShapes(ids) => self.canvas_handler.delte_shapes(&ids),
-                MoveShapes(ids, dir) => self.canvas_handler.move_shapes(&ids, dir),
-                Nop => {}
+            terminal.draw(|f| self.render(f))?;
+            if !event::poll(IDLE_TIME)? {
+                self.on_idle();
+                continue;
+            }
+            let event = event::read()?;
+            self.notice = None;
+
+            let cursor = self.canvas_handler.cursor_coord();
+            let mut walked_jump_list = false;
+            self.mode.process_event(event);
+            // Changes made by an event, e.g. with a count or by a macro, are undone at once.
+            self.history.begin_group();
+            while let Some(op) = self.mode.next_op(&self.canvas_handler) {
+                match op {
+                    QuitApp(force) => {
+                        if force || !self.changed {
+                            return Ok(());
+                        }
+                        self.notice = Some(NOT_WRITTEN.to_string());
+                    }
+                    MakeShape(c, s) => {
+                        let id = self.canvas_handler.add_shape(c, s);
+                        self.history
+                            .record(Change::Delete([id].into_iter().collect()));
+                        self.set_changed();
+                    }
+                    MakeShapes(shapes) => self.add_shapes(shapes),
+                    MoveCanvasCursor(d) => self.canvas_handler.move_cursor(d),
+                    SetCanvasCursor(c) => self.canvas_handler.set_cursor(c),
+                    DeleteShapes(ids) => {
+                        let deleted = self.canvas_handler.delte_shapes(&ids);
+                        self.history.record(Change::Insert(deleted));
+                        self.set_changed();
+                    }
+                    MoveShapes(ids, dir) => {
+                        self.canvas_handler.move_shapes(&ids, dir);
+                        self.history.record(Change::Move(ids, dir.opposite()));
+                        self.set_changed();
+                    }
+                    WriteDocument(storage) => {
+                        self.notice = Some(match self.write(storage) {
+                            Ok(storage) => format!("\"{}\" written", storage),
+                            Err(e) => format!("{:#}", e),
+                        });
+                    }
+                    WriteDocumentAndQuit(storage) => match self.write(storage) {
+                        Ok(_) => return Ok(()),
+                        Err(e) => self.notice = Some(format!("{:#}", e)),
+                    },
+                    EditDocument(_, false) if self.changed => {
+                        self.notice = Some(NOT_WRITTEN.to_string());
+                    }
+                    EditDocument(storage, _) => {
+                        if let Err(e) = self.load(storage) {
+                            self.notice = Some(format!("{:#}", e));
+                        }
+                    }
+                    ReloadDocument => {
+                        if let Err(e) = self.reload() {
+                            self.notice = Some(format!("{:#}", e));
+                        }
+                    }
+                    MergeDocument => {
+                        self.notice = Some(match self.merge() {
+                            Ok((r, a)) => format!("{} shapes removed, {} shapes added", r, a),
+                            Err(e) => format!("{:#}", e),
+                        });
+                    }
+                    ExportCanvas(path, format) => {
+                        self.notice = Some(match self.export(&path, format) {
+                            Ok(()) => format!("\"{}\" exported", path.display()),
+                            Err(e) => format!("{:#}", e),
+                        });
+                    }
+                    ImportFile(path, style) => {
+                        self.notice = Some(match self.import(&path, style) {
+                            Ok((n, 0)) => format!("\"{}\" {} shapes imported", path.display(), n),
+                            Ok((n, loops)) => format!(
+                                "\"{}\" {} shapes imported, {} edges from a node to itself are not drawn",
+                                path.display(),
+                                n,
+                                loops
+                            ),
+                            Err(e) => format!("{:#}", e),
+                        });
+                    }
+                    RecoverSwap => {
+                        self.notice = Some(match self.recover() {
+                            Ok(path) => format!("\"{}\" recovered", path.display()),
+                            Err(e) => format!("{:#}", e),
+                        });
+                    }
+                    DiscardSwap => {
+                        self.notice = Some(match self.discard_swap() {
+                            Ok(path) => format!("\"{}\" removed", path.display()),
+                            Err(e) => format!("{:#}", e),
+                        });
+                    }
+                    YankText(ids) => {
+                        let n = self.yank(terminal.backend_mut(), ids.as_ref())?;
+                        self.notice = Some(format!("{} lines yanked", n));
+                    }
+                    YankShapes(register, ids) => {
+                        let canvas = self.canvas_handler.canvas();
+                        let n = self.registers.yank(register, canvas, &ids);
+                        self.notice = Some(format!("{} shapes yanked", n));
+                    }
+                    PutShapes(register, before) => {
+                        let cursor = self.canvas_handler.cursor_coord();
+                        match self.registers.put(register, cursor, before) {
+                            Some(shapes) => self.add_shapes(shapes),
+                            None => {
+                                let register = register.unwrap_or(register::UNNAMED);
+                                self.notice = Some(format!("Nothing in register {}", register));
+                            }
+                        }
+                    }
+                    Undo => {
+                        if self.history.undo(&mut self.canvas_handler) {
+                            self.set_changed();
+                        } else {
+                            self.notice = Some("Already at oldest change".to_string());
+                        }
+                    }
+                    Redo => {
+                        if self.history.redo(&mut self.canvas_handler) {
+                            self.set_changed();
+                        } else {
+                            self.notice = Some("Already at newest change".to_string());
+                        }
+                    }
+                    SetMark(name) => self.canvas_handler.set_mark(name),
+                    JumpOlder => {
+                        walked_jump_list = true;
+                        if !self.canvas_handler.jump_older() {
+                            self.notice = Some("Already at oldest jump".to_string());
+                        }
+                    }
+                    JumpNewer => {
+                        walked_jump_list = true;
+                        if !self.canvas_handler.jump_newer() {
+                            self.notice = Some("Already at newest jump".to_string());
+                        }
+                    }
+                    ShowNotice(msg) => self.notice = Some(msg),
+                    Nop => {}
+                }
+            }
+            self.history.end_group();
+            if !walked_jump_list {
+                self.canvas_handler.record_jump(cursor);
             }
         }
     }
 
+    /// Write the canvas to the swap file of this app, or to a file in the temporary
+    /// directory if the document has no swap file, and return the path.
+    /// Used to save the canvas when the app panics.
+    fn dump(&mut self) -> anyhow::Result<PathBuf> {
+        let path = match self.own_swap() {
+            Some(path) => path,
+            None => std::env::temp_dir().join(format!("uart-{}.uart", std::process::id())),
+        };
+        swap::write(&path, self.canvas_handler.canvas())?;
+        // Keep the file for the next session.
+        self.swap = None;
+        Ok(path)
+    }
+
     /// Run the application.
-    pub fn run(&mut self) {
-        // Setup terminal
-        enable_raw_mode().unwrap();
-        let mut stdout = std::io::stdout();
-        execute!(stdout, EnterAlternateScreen).unwrap();
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).unwrap();
-
-        // create app and run it
-        self.main_loop(&mut terminal);
-
-        // restore terminal
-        disable_raw_mode().unwrap();
-        execute!(terminal.backend_mut(), LeaveAlternateScreen,).unwrap();
-        terminal.show_cursor().unwrap();
+    ///
+    /// The terminal is restored even if the app panics, and the canvas is dumped (see `dump`)
+    /// so that it can be recovered.
+    pub fn run(&mut self) -> anyhow::Result<()> {
+        let mut terminal = setup_terminal()
+            .inspect_err(|_| {
+                let _ = restore_terminal();
+            })
+            .context("failed to setup terminal")?;
+
+        let prev_hook = Arc::new(std::panic::take_hook());
+        let hook = Arc::clone(&prev_hook);
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = restore_terminal();
+            hook(info);
+        }));
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| self.main_loop(&mut terminal)));
+        let restored = restore_terminal();
+        // Put back the previous hook.
+        std::panic::set_hook(Box::new(move |info| prev_hook(info)));
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                let dumped = match self.dump() {
+                    Ok(path) => format!(", the canvas is saved to {}", path.display()),
+                    Err(_) => String::new(),
+                };
+                anyhow::bail!("terminal I/O error: {}{}", e, dumped);
+            }
+            Err(panic) => {
+                match self.dump() {
+                    Ok(path) => eprintln!("uart: the canvas is saved to {}", path.display()),
+                    Err(e) => eprintln!("uart: {:#}", e),
+                }
+                std::panic::resume_unwind(panic);
+            }
+        }
+        restored.context("failed to restore terminal")?;
+        self.remove_swap();
+
+        if let Some(Storage::Pipe {
+            output: Some(output),
+            ..
+        }) = &self.storage
+        {
+            print!("{}", output);
+        }
+        Ok(())
+    }
+}
+
+/// Output to draw the app.
+/// If stdout is not a terminal, e.g. in pipe mode, the app is drawn on `/dev/tty`.
+fn terminal_output() -> std::io::Result<Box<dyn Write>> {
+    if std::io::stdout().is_terminal() {
+        Ok(Box::new(std::io::stdout()))
+    } else {
+        Ok(Box::new(File::options().write(true).open("/dev/tty")?))
     }
 }
+
+fn setup_terminal() -> std::io::Result<Terminal<impl Backend + Write>> {
+    enable_raw_mode()?;
+    let mut output = terminal_output()?;
+    execute!(output, EnterAlternateScreen, EnableBracketedPaste)?;
+    Terminal::new(CrosstermBackend::new(output))
+}
+
+fn restore_terminal() -> std::io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal_output()?,
+        DisableBracketedPaste,
+        LeaveAlternateScreen,
+        cursor::Show
+    )
+}
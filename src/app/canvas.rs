@@ -1,12 +1,17 @@
 pub mod cursor;
 pub mod handler;
+mod jump;
 
 use self::cursor::Cursor;
 use crate::{
     app::shape::Shape,
-    util::{Coord, Direction, Id, IdGenerator},
+    util::{BufferExt, Coord, Direction, Id, IdGenerator, Size},
 };
 pub use handler::*;
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::Color,
+};
 use std::collections::BTreeMap;
 
 #[derive(PartialEq, Eq, Hash, Copy, Clone, Ord, PartialOrd, Debug)]
@@ -24,13 +29,25 @@ pub struct Canvas {
 impl Canvas {
     /// Add new shape to canvas.
     /// `coord` is the coord of upper-left corner of the shape.
-    pub fn add_shape(&mut self, coord: Coord, shape: Box<dyn Shape>) {
+    pub fn add_shape(&mut self, coord: Coord, shape: Box<dyn Shape>) -> ShapeId {
         let id = self.sig.gen();
+        self.insert_shape(id, coord, shape);
+        id
+    }
+
+    /// Put the shape with the id which was generated by this canvas.
+    /// The z-order of the shape is decided by the id.
+    fn insert_shape(&mut self, id: ShapeId, coord: Coord, shape: Box<dyn Shape>) {
         let old = self.shapes.insert(id, (coord, shape));
         // Ensure there is no shape which has same id.
         assert!(old.is_none());
     }
 
+    /// Shapes ordered from back to front.
+    pub fn shapes(&self) -> impl Iterator<Item = &(Coord, Box<dyn Shape>)> {
+        self.shapes.values()
+    }
+
     pub fn cursor(&self) -> &Cursor {
         &self.cursor
     }
@@ -49,7 +66,181 @@ impl Canvas {
         }
     }
 
-    fn delete_shape(&mut self, id: ShapeId) {
-        self.shapes.remove(&id);
+    fn delete_shape(&mut self, id: ShapeId) -> Option<(Coord, Box<dyn Shape>)> {
+        self.shapes.remove(&id)
+    }
+
+    /// Keep only the shapes for which `f` returns true, and return the removed shapes.
+    fn retain_shapes(
+        &mut self,
+        mut f: impl FnMut(Coord, &dyn Shape) -> bool,
+    ) -> Vec<(ShapeId, Coord, Box<dyn Shape>)> {
+        let ids: Vec<ShapeId> = self
+            .shapes
+            .iter()
+            .filter(|(_, (c, s))| !f(*c, s.as_ref()))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| self.delete_shape(id).map(|(c, s)| (id, c, s)))
+            .collect()
+    }
+}
+
+// Methods for export.
+impl Canvas {
+    /// Copy of the canvas which has only the shapes in `ids`.
+    pub fn subset(&self, ids: &ShapeIdSet) -> Canvas {
+        self.shapes
+            .iter()
+            .filter(|(id, _)| ids.contains(id))
+            .map(|(_, (c, s))| (*c, s.clone_box()))
+            .collect()
+    }
+
+    /// Upper-left corner and size of the smallest area which covers all shapes.
+    pub fn bounding_box(&self) -> Option<(Coord, Size)> {
+        let (min, max) = self
+            .shapes()
+            .filter(|(_, s)| s.size().width > 0 && s.size().height > 0)
+            .map(|(c, s)| {
+                let size = s.size();
+                (
+                    *c,
+                    Coord::new(c.x + size.width as i16, c.y + size.height as i16),
+                )
+            })
+            .reduce(|(min_a, max_a), (min_b, max_b)| {
+                (
+                    Coord::new(min_a.x.min(min_b.x), min_a.y.min(min_b.y)),
+                    Coord::new(max_a.x.max(max_b.x), max_a.y.max(max_b.y)),
+                )
+            })?;
+
+        let size = Size::new((max.x - min.x) as u16, (max.y - min.y) as u16);
+        Some((min, size))
+    }
+
+    /// Composite all shapes in z-order into a buffer which just covers them.
+    /// Shapes are drawn in the default color of terminals, with their attributes such as bold.
+    pub fn composite(&self) -> Buffer {
+        self.composite_with_ids().0
+    }
+
+    /// Composite all shapes like `composite`, and also return the id of the shape
+    /// drawn on each cell, indexed by `[y][x]`.
+    pub fn composite_with_ids(&self) -> (Buffer, Vec<Vec<Option<ShapeId>>>) {
+        let Some((origin, size)) = self.bounding_box() else {
+            return (Buffer::empty(Default::default()), vec![]);
+        };
+
+        let area = ratatui::layout::Rect::new(0, 0, size.width, size.height);
+        let mut buf = Buffer::empty(area);
+        let mut ids = vec![vec![None; size.width as usize]; size.height as usize];
+
+        // Symbol which is never drawn by shapes, to find the cells drawn by a shape.
+        const UNDRAWN: &str = "\0";
+
+        for (id, (coord, shape)) in &self.shapes {
+            let offset = coord.offset(origin);
+            shape.render(offset, area, &mut buf, Color::Reset);
+
+            let size = shape.size();
+            let shape_area = ratatui::layout::Rect::new(0, 0, size.width, size.height);
+            let mut shape_buf = Buffer::filled(shape_area, Cell::new(UNDRAWN));
+            shape.render(Coord::default(), shape_area, &mut shape_buf, Color::Reset);
+
+            for p in shape_area.positions() {
+                if shape_buf[p].symbol() != UNDRAWN {
+                    let x = (offset.x + p.x as i16) as usize;
+                    let y = (offset.y + p.y as i16) as usize;
+                    ids[y][x] = Some(*id);
+                }
+            }
+        }
+        (buf, ids)
+    }
+
+    /// Render all shapes into a plain text trimmed to their bounding box.
+    /// Trailing whitespaces of each line are stripped.
+    pub fn to_plain_text(&self) -> String {
+        let buf = self.composite();
+        let area = buf.area;
+
+        let mut text = String::new();
+        for y in area.top()..area.bottom() {
+            let line: String = buf.row_symbols(y).into_iter().map(|(_, s)| s).collect();
+            text += line.trim_end();
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Render all shapes into a plain text like `to_plain_text`, but from the origin of the
+    /// canvas instead of the bounding box, so that empty rows and columns before the shapes are
+    /// kept.
+    pub fn to_plain_text_from_origin(&self) -> String {
+        let Some((origin, _)) = self.bounding_box() else {
+            return String::new();
+        };
+        let indent = " ".repeat(origin.x.max(0) as usize);
+        let rows = "\n".repeat(origin.y.max(0) as usize);
+        let lines: String = self
+            .to_plain_text()
+            .lines()
+            .map(|l| match l {
+                "" => "\n".to_string(),
+                l => format!("{}{}\n", indent, l),
+            })
+            .collect();
+        rows + &lines
+    }
+}
+
+impl FromIterator<(Coord, Box<dyn Shape>)> for Canvas {
+    /// Make canvas from shapes ordered from back to front.
+    fn from_iter<T: IntoIterator<Item = (Coord, Box<dyn Shape>)>>(iter: T) -> Self {
+        let mut canvas = Self::default();
+        iter.into_iter().for_each(|(coord, shape)| {
+            canvas.add_shape(coord, shape);
+        });
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::shape::{rect::Rect, style::Style, text::Text},
+        util::Size,
+    };
+
+    fn canvas() -> Canvas {
+        [
+            (
+                Coord::new(2, 1),
+                Box::new(Rect::new(Size::new(4, 3), Style::Single)) as Box<dyn Shape>,
+            ),
+            (Coord::new(3, 2), Box::new(Text::new("x".to_string()))),
+            (Coord::new(8, 1), Box::new(Text::new("ab".to_string()))),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn plain_text_in_bounding_box() {
+        assert_eq!(canvas().to_plain_text(), "┌──┐  ab\n│x │\n└──┘\n");
+        assert_eq!(Canvas::default().to_plain_text(), "");
+    }
+
+    #[test]
+    fn plain_text_from_origin() {
+        assert_eq!(
+            canvas().to_plain_text_from_origin(),
+            "\n  ┌──┐  ab\n  │x │\n  └──┘\n"
+        );
+        assert_eq!(Canvas::default().to_plain_text_from_origin(), "");
     }
 }
@@ -1,9 +1,10 @@
-use super::{cursor::Cursor, Canvas, ShapeId, ShapeTag};
+use super::{cursor::Cursor, jump::JumpList, Canvas, ShapeId, ShapeTag};
 use crate::{
     app::shape::Shape,
     util::{Coord, Direction, IdSet, Size},
 };
 use ratatui::{style::Color, widgets::Widget};
+use std::collections::HashMap;
 
 pub type ShapeIdSet = IdSet<ShapeTag>;
 
@@ -18,11 +19,31 @@ pub struct CanvasHandler {
     rendering_size: Size,
     additional_shapes: Vec<(Coord, Box<dyn Shape>)>,
     shapes_to_highlight: ShapeIdSet,
+    /// Positions marked by user.
+    marks: HashMap<char, Coord>,
+    jump_list: JumpList,
 }
 
 impl CanvasHandler {
-    pub fn add_shape(&mut self, coord: Coord, shape: Box<dyn Shape>) {
-        self.canvas.add_shape(coord, shape);
+    pub fn add_shape(&mut self, coord: Coord, shape: Box<dyn Shape>) -> ShapeId {
+        self.canvas.add_shape(coord, shape)
+    }
+
+    /// Put back the shapes removed from the canvas with their ids.
+    pub fn insert_shapes(&mut self, shapes: Vec<(ShapeId, Coord, Box<dyn Shape>)>) {
+        for (id, coord, shape) in shapes {
+            self.canvas.insert_shape(id, coord, shape);
+        }
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Replace the whole canvas.
+    pub fn set_canvas(&mut self, canvas: Canvas) {
+        self.canvas = canvas;
+        self.rendering_offset = Coord::default();
     }
 
     pub fn cursor(&self) -> &Cursor {
@@ -42,6 +63,46 @@ impl CanvasHandler {
     }
 }
 
+// Methods for marks and jumps.
+impl CanvasHandler {
+    /// Mark the cursor position with the name.
+    pub fn set_mark(&mut self, name: char) {
+        self.marks.insert(name, self.cursor_coord());
+    }
+
+    pub fn mark(&self, name: char) -> Option<Coord> {
+        self.marks.get(&name).copied()
+    }
+
+    /// Record the move of the cursor from `from` as a jump if it is far enough.
+    pub fn record_jump(&mut self, from: Coord) {
+        self.jump_list.record(from, self.cursor_coord());
+    }
+
+    /// Go back to the position before the jump. Return false if there is no older jump.
+    pub fn jump_older(&mut self) -> bool {
+        let current = self.cursor_coord();
+        match self.jump_list.older(current) {
+            Some(coord) => {
+                self.set_cursor(coord);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Go forward after `jump_older`. Return false if there is no newer jump.
+    pub fn jump_newer(&mut self) -> bool {
+        match self.jump_list.newer() {
+            Some(coord) => {
+                self.set_cursor(coord);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 // Methods for rendering.
 impl CanvasHandler {
     pub fn set_rendering_size(&mut self, size: Size) {
@@ -127,8 +188,19 @@ impl CanvasHandler {
         ids.iter().for_each(|id| self.canvas.move_shape(id, dir));
     }
 
-    pub fn delte_shapes(&mut self, ids: &ShapeIdSet) {
-        ids.iter().for_each(|i| self.canvas.delete_shape(i));
+    /// Delete the shapes and return them.
+    pub fn delte_shapes(&mut self, ids: &ShapeIdSet) -> Vec<(ShapeId, Coord, Box<dyn Shape>)> {
+        ids.iter()
+            .filter_map(|i| self.canvas.delete_shape(i).map(|(c, s)| (i, c, s)))
+            .collect()
+    }
+
+    /// Keep only the shapes for which `f` returns true, and return the removed shapes.
+    pub fn retain_shapes(
+        &mut self,
+        f: impl FnMut(Coord, &dyn Shape) -> bool,
+    ) -> Vec<(ShapeId, Coord, Box<dyn Shape>)> {
+        self.canvas.retain_shapes(f)
     }
 }
 
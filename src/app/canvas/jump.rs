@@ -0,0 +1,107 @@
+use crate::util::Coord;
+
+/// Min distance of a cursor move to be recorded as a jump.
+const JUMP_DISTANCE: u16 = 10;
+
+/// Max number of jumps to remember.
+const MAX_JUMPS: usize = 100;
+
+/// Positions where the cursor jumped from, like the jump list of vim.
+#[derive(Default)]
+pub struct JumpList {
+    jumps: Vec<Coord>,
+    /// Position in the list while walking it.
+    /// Equal to the length of the list if not walking.
+    index: usize,
+}
+
+impl JumpList {
+    /// Record the move from `from` to `to` if it is far enough.
+    pub fn record(&mut self, from: Coord, to: Coord) {
+        if from.x.abs_diff(to.x).max(from.y.abs_diff(to.y)) < JUMP_DISTANCE {
+            return;
+        }
+        self.jumps.retain(|c| *c != from);
+        self.jumps.push(from);
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.index = self.jumps.len();
+    }
+
+    /// Position to go back from `current`.
+    pub fn older(&mut self, current: Coord) -> Option<Coord> {
+        if self.index == 0 {
+            return None;
+        }
+        // Remember where the walk started to go forward to it.
+        if self.index == self.jumps.len() {
+            self.jumps.retain(|c| *c != current);
+            self.jumps.push(current);
+            self.index = self.jumps.len() - 1;
+            if self.index == 0 {
+                return None;
+            }
+        }
+        self.index -= 1;
+        Some(self.jumps[self.index])
+    }
+
+    /// Position to go forward after going back.
+    pub fn newer(&mut self) -> Option<Coord> {
+        if self.index + 1 >= self.jumps.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.jumps[self.index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_jumps() {
+        let (a, b, c) = (Coord::new(0, 0), Coord::new(20, 0), Coord::new(20, 30));
+        let mut jumps = JumpList::default();
+        jumps.record(a, Coord::new(9, 9));
+        assert_eq!(jumps.older(Coord::new(9, 9)), None);
+
+        jumps.record(a, b);
+        jumps.record(b, c);
+        assert_eq!(jumps.older(c), Some(b));
+        assert_eq!(jumps.older(b), Some(a));
+        assert_eq!(jumps.older(a), None);
+        assert_eq!(jumps.newer(), Some(b));
+        assert_eq!(jumps.newer(), Some(c));
+        assert_eq!(jumps.newer(), None);
+    }
+
+    #[test]
+    fn keep_each_position_once() {
+        let (a, b) = (Coord::new(0, 0), Coord::new(0, 10));
+        let mut jumps = JumpList::default();
+        jumps.record(a, b);
+        jumps.record(b, a);
+        jumps.record(a, b);
+        assert_eq!(jumps.older(b), Some(a));
+        assert_eq!(jumps.older(a), None);
+    }
+
+    #[test]
+    fn forget_old_jumps() {
+        let mut jumps = JumpList::default();
+        for i in 0..=MAX_JUMPS as i16 {
+            jumps.record(Coord::new(0, i * 10), Coord::new(100, 0));
+        }
+        let mut current = Coord::new(100, 0);
+        let mut walked = 0;
+        while let Some(c) = jumps.older(current) {
+            current = c;
+            walked += 1;
+        }
+        assert_eq!(walked, MAX_JUMPS);
+        assert_eq!(current, Coord::new(0, 10));
+    }
+}
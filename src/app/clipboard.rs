@@ -0,0 +1,25 @@
+//! Copy to the system clipboard through the terminal emulator.
+//!
+//! The text is sent with the OSC 52 escape sequence, so that it reaches the clipboard of the
+//! local machine even over SSH.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::io::Write;
+
+/// Write the OSC 52 sequence which sets the clipboard to the text.
+pub fn copy(w: &mut impl Write, text: &str) -> std::io::Result<()> {
+    write!(w, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_writes_osc52() {
+        let mut out = vec![];
+        copy(&mut out, "hi").unwrap();
+        assert_eq!(out, b"\x1b]52;c;aGk=\x07");
+    }
+}
@@ -0,0 +1,107 @@
+//! Native document format.
+//!
+//! ```text
+//! uart 1
+//! 0 0 rect 10 5 single
+//! 9 2 path dash > llllljj
+//! 2 2 text hello\nworld
+//! 0 6 table single 2 name|age|bob|42
+//! ```
+//!
+//! The first line is the header with the format version.
+//! Each following line is a shape: `<x> <y> <record>`, where `<x> <y>` is the coord of the
+//! upper-left corner of the shape and `<record>` is encoded by `Shape::encode`.
+//! Shapes are listed in z-order (from back to front).
+//! Empty lines and lines starting with `#` are ignored.
+
+use super::shape::{path::Path, rect::Rect, table::Table, text::Text, Shape};
+use crate::util::Coord;
+use anyhow::{bail, ensure, Context};
+
+const MAGIC: &str = "uart";
+const VERSION: u32 = 1;
+
+/// Encode shapes (ordered from back to front) into a document.
+pub fn encode<'a>(shapes: impl Iterator<Item = &'a (Coord, Box<dyn Shape>)>) -> String {
+    let mut doc = format!("{} {}\n", MAGIC, VERSION);
+    for (coord, shape) in shapes {
+        doc += &format!("{} {} {}\n", coord.x, coord.y, shape.encode());
+    }
+    doc
+}
+
+/// Decode a document into shapes ordered from back to front.
+pub fn decode(doc: &str) -> anyhow::Result<Vec<(Coord, Box<dyn Shape>)>> {
+    let mut lines = doc.lines().enumerate();
+
+    let header = lines.next().map(|(_, l)| l).unwrap_or_default();
+    let Some(version) = header.strip_prefix(MAGIC).map(str::trim) else {
+        bail!("not a uart document");
+    };
+    let version: u32 = version.parse().context("malformed header")?;
+    ensure!(
+        version == VERSION,
+        "unsupported document version {}",
+        version
+    );
+
+    lines
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(i, l)| decode_line(l).with_context(|| format!("line {}", i + 1)))
+        .collect()
+}
+
+fn decode_line(line: &str) -> anyhow::Result<(Coord, Box<dyn Shape>)> {
+    let mut fields = line.splitn(3, ' ');
+    let (Some(x), Some(y), Some(record)) = (fields.next(), fields.next(), fields.next()) else {
+        bail!("malformed shape");
+    };
+    let coord = Coord::new(x.parse()?, y.parse()?);
+    Ok((coord, decode_shape(record)?))
+}
+
+/// Decode a record made by `Shape::encode`.
+pub fn decode_shape(record: &str) -> anyhow::Result<Box<dyn Shape>> {
+    let kind = record.split(' ').next().unwrap_or_default();
+    let shape: Box<dyn Shape> = match kind {
+        "rect" => Box::new(record.parse::<Rect>()?),
+        "path" => Box::new(record.parse::<Path>()?),
+        "text" => Box::new(record.parse::<Text>()?),
+        "table" => Box::new(record.parse::<Table>()?),
+        _ => bail!("unknown shape `{}`", kind),
+    };
+    Ok(shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "uart 1
+0 0 rect 10 5 single
+9 2 path dash > llllljj
+2 2 text hello\\nworld
+0 6 table single 2 name|age|bob|42
+";
+
+    #[test]
+    fn round_trip() {
+        let shapes = decode(DOC).unwrap();
+        assert_eq!(shapes.len(), 4);
+        assert_eq!(shapes[2].0, Coord::new(2, 2));
+        assert_eq!(encode(shapes.iter()), DOC);
+    }
+
+    #[test]
+    fn skip_comments_and_empty_lines() {
+        let shapes = decode("uart 1\n\n# comment\n1 2 rect 3 3 double\n").unwrap();
+        assert_eq!(encode(shapes.iter()), "uart 1\n1 2 rect 3 3 double\n");
+    }
+
+    #[test]
+    fn reject_unknown_header() {
+        assert!(decode("uart 2\n").is_err());
+        assert!(decode("0 0 rect 3 3 single\n").is_err());
+        assert!(decode("uart 1\n0 0 circle 3\n").is_err());
+    }
+}
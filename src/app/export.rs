@@ -0,0 +1,64 @@
+//! Export of the canvas to other formats.
+
+pub mod ansi;
+pub mod html;
+pub mod svg;
+
+use super::{canvas::Canvas, scene};
+use std::path::Path;
+
+/// Format to export.
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Format {
+    /// Flattened plain text.
+    PlainText,
+    /// SVG with rects and paths drawn as vector lines.
+    Svg,
+    /// HTML `<pre>` snippet, optionally with CSS.
+    Html { css: bool },
+    /// Plain text with SGR escape codes.
+    Ansi,
+    /// JSON scene (see `scene`).
+    Scene,
+}
+
+impl Format {
+    /// Guess the format from the extension of the file.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => Format::Svg,
+            Some("html" | "htm") => Format::Html { css: false },
+            Some("ans" | "ansi") => Format::Ansi,
+            Some("json") => Format::Scene,
+            _ => Format::PlainText,
+        }
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = anyhow::Error;
+
+    /// Parse the name of the format.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "txt" | "text" => Ok(Format::PlainText),
+            "svg" => Ok(Format::Svg),
+            "html" => Ok(Format::Html { css: false }),
+            "html-css" => Ok(Format::Html { css: true }),
+            "ansi" => Ok(Format::Ansi),
+            "json" => Ok(Format::Scene),
+            _ => anyhow::bail!("unknown format `{}`", s),
+        }
+    }
+}
+
+/// Export the canvas in the format.
+pub fn export(canvas: &Canvas, format: Format) -> String {
+    match format {
+        Format::PlainText => canvas.to_plain_text(),
+        Format::Svg => svg::export(canvas),
+        Format::Html { css } => html::export(canvas, css),
+        Format::Ansi => ansi::export(canvas),
+        Format::Scene => scene::encode(canvas.shapes()),
+    }
+}
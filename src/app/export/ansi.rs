@@ -0,0 +1,142 @@
+//! ANSI-colored text export.
+//!
+//! Each character is written with SGR escape codes of the color and the modifiers of its cell,
+//! so that the exported file can be shown in terminals by `cat`.
+
+use crate::{app::canvas::Canvas, util::BufferExt};
+use ratatui::style::{Color, Modifier};
+
+const MODIFIERS: [(Modifier, u8); 9] = [
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::SLOW_BLINK, 5),
+    (Modifier::RAPID_BLINK, 6),
+    (Modifier::REVERSED, 7),
+    (Modifier::HIDDEN, 8),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+/// SGR parameters of the color.
+/// `base` is 30 for foreground colors, and 40 for background colors.
+fn color_params(color: Color, base: u8) -> Option<String> {
+    let basic = |offset: u8| Some((base + offset).to_string());
+    let bright = |offset: u8| Some((base + 60 + offset).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => basic(0),
+        Color::Red => basic(1),
+        Color::Green => basic(2),
+        Color::Yellow => basic(3),
+        Color::Blue => basic(4),
+        Color::Magenta => basic(5),
+        Color::Cyan => basic(6),
+        Color::Gray => basic(7),
+        Color::DarkGray => bright(0),
+        Color::LightRed => bright(1),
+        Color::LightGreen => bright(2),
+        Color::LightYellow => bright(3),
+        Color::LightBlue => bright(4),
+        Color::LightMagenta => bright(5),
+        Color::LightCyan => bright(6),
+        Color::White => bright(7),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+        Color::Indexed(i) => Some(format!("{};5;{}", base + 8, i)),
+    }
+}
+
+/// SGR escape sequence which resets the attributes and sets the new ones.
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let params: Vec<String> = std::iter::once("0".to_string())
+        .chain(
+            MODIFIERS
+                .iter()
+                .filter(|(m, _)| modifier.contains(*m))
+                .map(|(_, p)| p.to_string()),
+        )
+        .chain(color_params(fg, 30))
+        .chain(color_params(bg, 40))
+        .collect();
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// Export the canvas as a text with SGR escape codes.
+pub fn export(canvas: &Canvas) -> String {
+    let buf = canvas.composite();
+    let plain = (Color::Reset, Color::Reset, Modifier::empty());
+    let mut text = String::new();
+
+    for y in buf.area.top()..buf.area.bottom() {
+        let symbols = buf.row_symbols(y);
+        // Strip trailing whitespaces.
+        let len = symbols
+            .iter()
+            .rposition(|(_, s)| !s.trim().is_empty())
+            .map_or(0, |i| i + 1);
+
+        let mut current = plain;
+        for &(x, symbol) in &symbols[..len] {
+            let cell = &buf[(x, y)];
+            let attrs = (cell.fg, cell.bg, cell.modifier);
+            if attrs != current {
+                text += &sgr(attrs.0, attrs.1, attrs.2);
+                current = attrs;
+            }
+            text += symbol;
+        }
+        if current != plain {
+            text += &sgr(plain.0, plain.1, plain.2);
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::shape::{path::Path, rect::Rect, style::Style, text::Text, Shape},
+        util::{Coord, Direction, Size},
+    };
+
+    #[test]
+    fn sgr_of_attributes() {
+        assert_eq!(
+            sgr(Color::Red, Color::Rgb(1, 2, 3), Modifier::BOLD),
+            "\x1b[0;1;31;48;2;1;2;3m"
+        );
+        assert_eq!(
+            sgr(Color::White, Color::Indexed(8), Modifier::empty()),
+            "\x1b[0;97;48;5;8m"
+        );
+    }
+
+    #[test]
+    fn export_line_styles() {
+        let canvas: Canvas = [
+            (
+                Coord::new(0, 0),
+                Box::new(Rect::new(Size::new(4, 3), Style::SingleBold)) as Box<dyn Shape>,
+            ),
+            (
+                Coord::new(4, 1),
+                Box::new(Path::new(
+                    vec![Direction::Right, Direction::Right],
+                    false,
+                    true,
+                    Style::Dash,
+                )),
+            ),
+            (Coord::new(0, 3), Box::new(Text::new("a".to_string()))),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            export(&canvas),
+            "\x1b[0;1m┏━━┓\x1b[0m\n\x1b[0;1m┃  ┃\x1b[0;2m╌╌▶\x1b[0m\n\x1b[0;1m┗━━┛\x1b[0m\na\n"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! HTML export.
+//!
+//! The canvas is written as a `<pre>` block, and the characters drawn by each shape are
+//! wrapped in `<span data-shape-id="<id>">`.
+//! With CSS, colors are given inline and all spans of a shape are highlighted on hover.
+
+use crate::{
+    app::canvas::{Canvas, ShapeId},
+    util::BufferExt,
+};
+use std::collections::BTreeSet;
+
+const PRE_STYLE: &str = "color:#000;background:#fff;line-height:1.2";
+const HOVER_STYLE: &str = "background:#cde";
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Export the canvas as a `<pre>` snippet.
+pub fn export(canvas: &Canvas, css: bool) -> String {
+    let (buf, ids) = canvas.composite_with_ids();
+    let mut all_ids = BTreeSet::new();
+    let mut html = String::new();
+
+    if css {
+        html += &format!(r#"<pre class="uart" style="{}">"#, PRE_STYLE);
+    } else {
+        html += r#"<pre class="uart">"#;
+    }
+
+    for y in buf.area.top()..buf.area.bottom() {
+        let symbols = buf.row_symbols(y);
+        // Strip trailing whitespaces.
+        let len = symbols
+            .iter()
+            .rposition(|(_, s)| !s.trim().is_empty())
+            .map_or(0, |i| i + 1);
+
+        let mut current: Option<ShapeId> = None;
+        for &(x, symbol) in &symbols[..len] {
+            let id = ids[y as usize][x as usize];
+            if id != current {
+                if current.is_some() {
+                    html += "</span>";
+                }
+                if let Some(id) = id {
+                    html += &format!(r#"<span data-shape-id="{}">"#, id);
+                    all_ids.insert(id);
+                }
+                current = id;
+            }
+            html += &escape(symbol);
+        }
+        if current.is_some() {
+            html += "</span>";
+        }
+        html.push('\n');
+    }
+    html += "</pre>\n";
+
+    if css && !all_ids.is_empty() {
+        let rules: String = all_ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#".uart:has([data-shape-id="{id}"]:hover) [data-shape-id="{id}"]{{{}}}"#,
+                    HOVER_STYLE
+                )
+            })
+            .collect();
+        html = format!("<style>{}</style>\n{}", rules, html);
+    }
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::shape::{text::Text, Shape},
+        util::Coord,
+    };
+
+    fn canvas() -> Canvas {
+        [
+            (
+                Coord::new(0, 0),
+                Box::new(Text::new("a<b".to_string())) as Box<dyn Shape>,
+            ),
+            (Coord::new(5, 1), Box::new(Text::new("c".to_string()))),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn wrap_shapes_in_spans() {
+        assert_eq!(
+            export(&canvas(), false),
+            concat!(
+                r#"<pre class="uart"><span data-shape-id="0">a&lt;b</span>"#,
+                "\n",
+                r#"     <span data-shape-id="1">c</span>"#,
+                "\n</pre>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn highlight_shapes_with_css() {
+        let html = export(&canvas(), true);
+        assert!(html.starts_with("<style>"));
+        assert!(html.contains(
+            r#".uart:has([data-shape-id="1"]:hover) [data-shape-id="1"]{background:#cde}"#
+        ));
+        assert!(html.contains(&format!(r#"<pre class="uart" style="{}">"#, PRE_STYLE)));
+    }
+}
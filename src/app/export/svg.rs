@@ -0,0 +1,201 @@
+//! SVG export.
+//!
+//! Shapes are laid out on a grid of `CELL_WIDTH` x `CELL_HEIGHT` px cells.
+//! Lines of rects and paths are drawn through the centers of cells.
+
+use crate::{
+    app::{canvas::Canvas, shape::style::Style},
+    util::{Coord, Direction, Size},
+};
+use unicode_width::UnicodeWidthStr;
+
+const CELL_WIDTH: f32 = 10.0;
+const CELL_HEIGHT: f32 = 20.0;
+const FONT_SIZE: f32 = 16.0;
+const FOREGROUND: &str = "black";
+const BACKGROUND: &str = "white";
+
+/// Round px to 2 decimal places to keep the document short.
+fn round(px: f32) -> f32 {
+    (px * 100.0).round() / 100.0
+}
+
+fn x(cell: f32) -> f32 {
+    round(cell * CELL_WIDTH)
+}
+
+fn y(cell: f32) -> f32 {
+    round(cell * CELL_HEIGHT)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Rect filled with the background color to hide shapes behind.
+pub fn background(size: Size) -> String {
+    format!(
+        r#"<rect width="{}" height="{}" fill="{}"/>"#,
+        x(size.width as f32),
+        y(size.height as f32),
+        BACKGROUND
+    )
+}
+
+/// Lines of the text, one `<text>` per line.
+/// Each line is stretched to its width on the grid not to depend on the font.
+pub fn text(s: &str) -> String {
+    let mut svg = String::new();
+    for (row, line) in s.lines().enumerate() {
+        let content = line.trim_start();
+        if content.trim_end().is_empty() {
+            continue;
+        }
+        let indent = UnicodeWidthStr::width(&line[..line.len() - content.len()]);
+        svg += &format!(
+            r#"<text x="{}" y="{}" textLength="{}" lengthAdjust="spacingAndGlyphs">{}</text>"#,
+            x(indent as f32),
+            y(row as f32 + 0.8),
+            x(UnicodeWidthStr::width(content) as f32),
+            escape(content)
+        );
+    }
+    svg
+}
+
+/// Line through the centers of the cells.
+pub fn polyline(cells: &[Coord], style: Style, closed: bool) -> String {
+    let points = cells
+        .iter()
+        .map(|c| format!("{},{}", x(c.x as f32 + 0.5), y(c.y as f32 + 0.5)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let element = if closed { "polygon" } else { "polyline" };
+
+    // (stroke, width, dasharray) of each pass.
+    let passes: &[(&str, f32, &str)] = match style {
+        Style::Single | Style::Ascii => &[(FOREGROUND, 1.0, "none")],
+        Style::SingleBold => &[(FOREGROUND, 2.5, "none")],
+        Style::Double => &[(FOREGROUND, 4.0, "none"), (BACKGROUND, 1.5, "none")],
+        Style::Dash => &[(FOREGROUND, 1.0, "4 3")],
+        Style::Dot => &[(FOREGROUND, 2.0, "0 4")],
+    };
+
+    passes
+        .iter()
+        .map(|(stroke, width, dasharray)| {
+            format!(
+                r#"<{} points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-dasharray="{}" stroke-linecap="round"/>"#,
+                element, points, stroke, width, dasharray
+            )
+        })
+        .collect()
+}
+
+/// Arrowhead in the cell pointing to the direction.
+pub fn arrow(cell: Coord, dir: Direction) -> String {
+    let (cx, cy) = (cell.x as f32 + 0.5, cell.y as f32 + 0.5);
+    // Unit vector of the direction.
+    let (dx, dy) = match dir {
+        Direction::Up => (0.0, -1.0),
+        Direction::Down => (0.0, 1.0),
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0),
+    };
+    let point = |along: f32, across: f32| {
+        format!(
+            "{},{}",
+            x(cx + dx * along - dy * across),
+            y(cy + dy * along + dx * across)
+        )
+    };
+    format!(
+        r#"<polygon points="{} {} {}" fill="{}"/>"#,
+        point(0.5, 0.0),
+        point(-0.4, 0.4),
+        point(-0.4, -0.4),
+        FOREGROUND
+    )
+}
+
+/// Export the canvas as an SVG document.
+pub fn export(canvas: &Canvas) -> String {
+    let (origin, size) = canvas.bounding_box().unwrap_or_default();
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="{}">"#,
+        FONT_SIZE,
+        w = x(size.width as f32),
+        h = y(size.height as f32),
+    );
+    svg += "\n";
+    svg += &background(size);
+    svg += "\n";
+
+    for (coord, shape) in canvas.shapes() {
+        let offset = coord.offset(origin);
+        svg += &format!(
+            r#"<g transform="translate({},{})">{}</g>"#,
+            x(offset.x as f32),
+            y(offset.y as f32),
+            shape.svg()
+        );
+        svg += "\n";
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::shape::{path::Path, rect::Rect, text::Text, Shape};
+
+    #[test]
+    fn export_shapes() {
+        let canvas: Canvas = [
+            (
+                Coord::new(2, 1),
+                Box::new(Rect::new(Size::new(4, 3), Style::Single)) as Box<dyn Shape>,
+            ),
+            (Coord::new(3, 2), Box::new(Text::new("<".to_string()))),
+            (
+                Coord::new(6, 2),
+                Box::new(Path::new(
+                    vec![Direction::Right, Direction::Right],
+                    false,
+                    true,
+                    Style::Dash,
+                )),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let svg = export(&canvas);
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="70" height="60" viewBox="0 0 70 60""#
+        ));
+        assert!(svg.contains(r#"<g transform="translate(0,0)"><rect width="40" height="60" fill="white"/><polygon points="5,10 35,10 35,50 5,50""#));
+        assert!(svg.contains(r#"<g transform="translate(10,20)">"#));
+        assert!(svg.contains(">&lt;</text>"));
+        assert!(svg.contains(r#"<polyline points="5,10 15,10 25,10""#));
+        assert!(svg.contains(r#"<polygon points="30,10 21,18 21,2" fill="black"/>"#));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+    }
+
+    #[test]
+    fn export_empty_canvas() {
+        assert_eq!(
+            export(&Canvas::default()),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="0" height="0" viewBox="0 0 0 0" font-family="monospace" font-size="16">
+<rect width="0" height="0" fill="white"/>
+</svg>
+"#
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Undo and redo of the changes of the canvas.
+//!
+//! Each change applied to the canvas is recorded as its inverse.
+//! Undoing applies the inverse, and records the inverse of the inverse to redo.
+
+use super::{
+    canvas::{CanvasHandler, ShapeId, ShapeIdSet},
+    shape::Shape,
+};
+use crate::util::{Coord, Direction};
+
+/// Change of the shapes on the canvas.
+pub enum Change {
+    /// Put the shapes with their ids, which decide their z-order.
+    Insert(Vec<(ShapeId, Coord, Box<dyn Shape>)>),
+    /// Delete the shapes.
+    Delete(ShapeIdSet),
+    /// Move the shapes.
+    Move(ShapeIdSet, Direction),
+    /// Changes applied in order.
+    Batch(Vec<Change>),
+}
+
+impl Change {
+    fn is_empty(&self) -> bool {
+        match self {
+            Change::Insert(shapes) => shapes.is_empty(),
+            Change::Delete(ids) | Change::Move(ids, _) => ids.is_empty(),
+            Change::Batch(changes) => changes.iter().all(Change::is_empty),
+        }
+    }
+
+    /// Apply the change to the canvas and return its inverse.
+    fn apply(self, canvas_handler: &mut CanvasHandler) -> Change {
+        match self {
+            Change::Insert(shapes) => {
+                let ids = shapes.iter().map(|(id, _, _)| *id).collect();
+                canvas_handler.insert_shapes(shapes);
+                Change::Delete(ids)
+            }
+            Change::Delete(ids) => Change::Insert(canvas_handler.delte_shapes(&ids)),
+            Change::Move(ids, dir) => {
+                canvas_handler.move_shapes(&ids, dir);
+                Change::Move(ids, dir.opposite())
+            }
+            Change::Batch(changes) => {
+                let mut inverses: Vec<Change> = changes
+                    .into_iter()
+                    .map(|c| c.apply(canvas_handler))
+                    .collect();
+                inverses.reverse();
+                Change::Batch(inverses)
+            }
+        }
+    }
+}
+
+/// Stacks of the changes to undo and redo.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    /// Inverses of the changes recorded since the group began, if any.
+    group: Option<Vec<Change>>,
+}
+
+impl History {
+    /// Record the inverse of a change applied to the canvas.
+    /// The changes undone so far can no longer be redone.
+    pub fn record(&mut self, inverse: Change) {
+        if inverse.is_empty() {
+            return;
+        }
+        match &mut self.group {
+            Some(group) => group.push(inverse),
+            None => self.undo.push(inverse),
+        }
+        self.redo.clear();
+    }
+
+    /// Begin a group of changes, which are undone and redone at once.
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.group = Some(vec![]);
+    }
+
+    /// End the group of changes, and record them as one change.
+    pub fn end_group(&mut self) {
+        let Some(mut group) = self.group.take() else {
+            return;
+        };
+        // Undo the changes in the reverse order.
+        group.reverse();
+        match group.len() {
+            0 => {}
+            1 => self.undo.extend(group),
+            _ => self.undo.push(Change::Batch(group)),
+        }
+    }
+
+    /// Undo the last change. Return false if there is no change to undo.
+    /// The changes grouped so far are undone at once, and a new group begins.
+    pub fn undo(&mut self, canvas_handler: &mut CanvasHandler) -> bool {
+        if self.group.is_some() {
+            self.begin_group();
+        }
+        let Some(change) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(change.apply(canvas_handler));
+        true
+    }
+
+    /// Redo the last undone change. Return false if there is no change to redo.
+    pub fn redo(&mut self, canvas_handler: &mut CanvasHandler) -> bool {
+        if self.group.is_some() {
+            self.begin_group();
+        }
+        let Some(change) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(change.apply(canvas_handler));
+        true
+    }
+
+    /// Forget all changes, e.g. when the canvas is replaced.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        if let Some(group) = &mut self.group {
+            group.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::shape::text::Text;
+
+    /// Add a text at `x` and record the change.
+    fn add(canvas_handler: &mut CanvasHandler, history: &mut History, x: i16, s: &str) {
+        let id = canvas_handler.add_shape(Coord::new(x, 0), Box::new(Text::new(s.to_string())));
+        let mut ids = ShapeIdSet::default();
+        ids.insert(&id);
+        history.record(Change::Delete(ids));
+    }
+
+    fn text(canvas_handler: &CanvasHandler) -> String {
+        canvas_handler.canvas().to_plain_text()
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut canvas_handler = CanvasHandler::default();
+        let mut history = History::default();
+        add(&mut canvas_handler, &mut history, 0, "a");
+        add(&mut canvas_handler, &mut history, 2, "b");
+
+        assert!(history.undo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "a\n");
+        assert!(history.undo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "");
+        assert!(!history.undo(&mut canvas_handler));
+
+        assert!(history.redo(&mut canvas_handler));
+        assert!(history.redo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "a b\n");
+        assert!(!history.redo(&mut canvas_handler));
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut canvas_handler = CanvasHandler::default();
+        let mut history = History::default();
+        add(&mut canvas_handler, &mut history, 0, "a");
+        assert!(history.undo(&mut canvas_handler));
+        add(&mut canvas_handler, &mut history, 2, "b");
+        assert!(!history.redo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "b\n");
+    }
+
+    #[test]
+    fn deleted_shapes_come_back_in_z_order() {
+        let mut canvas_handler = CanvasHandler::default();
+        let mut history = History::default();
+        let back = canvas_handler.add_shape(Coord::new(0, 0), Box::new(Text::new("a".into())));
+        canvas_handler.add_shape(Coord::new(0, 0), Box::new(Text::new("b".into())));
+
+        let mut ids = ShapeIdSet::default();
+        ids.insert(&back);
+        let deleted = canvas_handler.delte_shapes(&ids);
+        history.record(Change::Insert(deleted));
+
+        assert!(history.undo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "b\n");
+        assert_eq!(canvas_handler.canvas().shapes().count(), 2);
+    }
+
+    #[test]
+    fn undo_group_at_once() {
+        let mut canvas_handler = CanvasHandler::default();
+        let mut history = History::default();
+        add(&mut canvas_handler, &mut history, 0, "a");
+        history.begin_group();
+        add(&mut canvas_handler, &mut history, 2, "b");
+        add(&mut canvas_handler, &mut history, 4, "c");
+        history.end_group();
+
+        assert!(history.undo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "a\n");
+        assert!(history.redo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "a b c\n");
+    }
+
+    #[test]
+    fn undo_ends_group() {
+        let mut canvas_handler = CanvasHandler::default();
+        let mut history = History::default();
+        history.begin_group();
+        add(&mut canvas_handler, &mut history, 0, "a");
+        add(&mut canvas_handler, &mut history, 2, "b");
+        assert!(history.undo(&mut canvas_handler));
+        assert_eq!(text(&canvas_handler), "");
+        add(&mut canvas_handler, &mut history, 4, "c");
+        history.end_group();
+
+        assert!(history.undo(&mut canvas_handler));
+        assert!(!history.undo(&mut canvas_handler));
+    }
+}
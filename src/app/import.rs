@@ -0,0 +1,375 @@
+//! Import of other formats onto the canvas.
+
+pub mod csv;
+pub mod dot;
+mod graph;
+pub mod mermaid;
+
+use super::shape::{
+    path::Path,
+    rect::Rect,
+    style::{Chips, Style},
+    text::Text,
+    Shape,
+};
+use crate::util::{Coord, Direction, Size};
+use unicode_width::UnicodeWidthChar;
+
+const TAB_WIDTH: usize = 8;
+
+/// Filler of the cells hidden by a wide char.
+const WIDE_TAIL: char = '\0';
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Left,
+    Direction::Right,
+    Direction::Down,
+];
+
+/// Character grid of a plain text.
+/// A wide char occupies its cell and the following `WIDE_TAIL` cells.
+struct Grid(Vec<Vec<char>>);
+
+impl Grid {
+    fn new(text: &str) -> Self {
+        let rows = text
+            .lines()
+            .map(|line| {
+                let mut row = vec![];
+                for c in line.chars() {
+                    let width = match (c, UnicodeWidthChar::width(c)) {
+                        ('\t', _) => TAB_WIDTH - row.len() % TAB_WIDTH,
+                        (c, Some(w)) if !c.is_control() && w > 0 => w,
+                        _ => continue,
+                    };
+                    if c.is_whitespace() {
+                        row.extend(std::iter::repeat_n(' ', width));
+                    } else {
+                        row.push(c);
+                        row.extend(std::iter::repeat_n(WIDE_TAIL, width - 1));
+                    }
+                }
+                row
+            })
+            .collect();
+        Self(rows)
+    }
+
+    fn get(&self, coord: Coord) -> char {
+        if coord.x < 0 || coord.y < 0 {
+            return ' ';
+        }
+        self.0
+            .get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .copied()
+            .unwrap_or(' ')
+    }
+
+    /// Clear the cells of the shape drawn at the coord.
+    fn clear(&mut self, coord: Coord, shape: &dyn Shape) {
+        for (dy, line) in shape.to_string().lines().enumerate() {
+            for (dx, c) in line.chars().enumerate() {
+                let y = (coord.y + dy as i16) as usize;
+                let x = (coord.x + dx as i16) as usize;
+                if c != ' ' {
+                    self.0[y][x] = ' ';
+                }
+            }
+        }
+    }
+
+    /// Return true if the shape drawn at the coord matches the grid.
+    fn matches(&self, coord: Coord, shape: &dyn Shape) -> bool {
+        shape.to_string().lines().enumerate().all(|(dy, line)| {
+            line.chars()
+                .enumerate()
+                .all(|(dx, c)| c == ' ' || self.get(coord + Coord::new(dx as i16, dy as i16)) == c)
+        })
+    }
+
+    /// Find rects drawn with the style.
+    fn take_rects(&mut self, style: Style) -> Vec<(Coord, Box<dyn Shape>)> {
+        let chips = style.chips();
+        let mut rects: Vec<(Coord, Box<dyn Shape>)> = vec![];
+
+        for y in 0..self.0.len() {
+            for x in 0..self.0[y].len() {
+                let coord = Coord::new(x as i16, y as i16);
+                if self.get(coord) != chips.upper_left_corner {
+                    continue;
+                }
+
+                // Candidates of the width and the height, larger one first.
+                let candidates = |dir: Direction, edge: char, corner: char| {
+                    let mut c = coord.adjacency(dir);
+                    let mut lengths = vec![];
+                    loop {
+                        if self.get(c) == corner {
+                            lengths.push((c.x - coord.x + c.y - coord.y + 1) as u16);
+                        }
+                        if self.get(c) != edge {
+                            break;
+                        }
+                        c = c.adjacency(dir);
+                    }
+                    lengths.reverse();
+                    lengths
+                };
+                let widths =
+                    candidates(Direction::Right, chips.horizontal, chips.upper_right_corner);
+                let heights = candidates(Direction::Down, chips.vertical, chips.lower_left_corner);
+
+                let rect = widths
+                    .iter()
+                    .flat_map(|&w| {
+                        heights
+                            .iter()
+                            .map(move |&h| Rect::new(Size::new(w, h), style))
+                    })
+                    .find(|rect| self.matches(coord, rect));
+
+                if let Some(rect) = rect {
+                    self.clear(coord, &rect);
+                    rects.push((coord, Box::new(rect)));
+                }
+            }
+        }
+
+        rects
+    }
+
+    /// Find paths drawn with the style.
+    fn take_paths(&mut self, style: Style) -> Vec<(Coord, Box<dyn Shape>)> {
+        let chips = style.chips();
+        // Ascii and dot chips often appear in plain texts, e.g. `a-b` and `...`.
+        let min_cells = match style {
+            Style::Ascii | Style::Dot => 3,
+            _ => 2,
+        };
+        let mut paths: Vec<(Coord, Box<dyn Shape>)> = vec![];
+
+        for y in 0..self.0.len() {
+            for x in 0..self.0[y].len() {
+                let start = Coord::new(x as i16, y as i16);
+                let degree = DIRECTIONS
+                    .iter()
+                    .filter(|&&d| self.connected(&chips, start, d))
+                    .count();
+                if degree != 1 {
+                    continue;
+                }
+
+                let (dirs, end) = self.trace(&chips, start);
+                if dirs.len() + 1 < min_cells {
+                    continue;
+                }
+
+                let is_arrow = |c: Coord| {
+                    let c = self.get(c);
+                    [
+                        chips.up_arrow,
+                        chips.down_arrow,
+                        chips.left_arrow,
+                        chips.right_arrow,
+                    ]
+                    .contains(&c)
+                };
+                let path = Path::new(dirs, is_arrow(start), is_arrow(end), style);
+                let coord = start + path.start_to_upper_left();
+                if self.matches(coord, &path) {
+                    self.clear(coord, &path);
+                    paths.push((coord, Box::new(path)));
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Directions to which the chip at the coord can be connected.
+    fn ports(&self, chips: &Chips, coord: Coord) -> Vec<Direction> {
+        use Direction::*;
+        let c = self.get(coord);
+        let roles = [
+            (chips.horizontal, vec![Left, Right]),
+            (chips.vertical, vec![Up, Down]),
+            (chips.upper_left_corner, vec![Right, Down]),
+            (chips.upper_right_corner, vec![Left, Down]),
+            (chips.lower_left_corner, vec![Up, Right]),
+            (chips.lower_right_corner, vec![Up, Left]),
+            (chips.up_arrow, vec![Down]),
+            (chips.down_arrow, vec![Up]),
+            (chips.left_arrow, vec![Right]),
+            (chips.right_arrow, vec![Left]),
+        ];
+        let mut ports: Vec<Direction> = roles
+            .into_iter()
+            .filter(|(chip, _)| *chip == c)
+            .flat_map(|(_, ports)| ports)
+            .collect();
+        ports.sort_by_key(|d| *d as u8);
+        ports.dedup();
+        ports
+    }
+
+    fn connected(&self, chips: &Chips, coord: Coord, dir: Direction) -> bool {
+        self.ports(chips, coord).contains(&dir)
+            && self
+                .ports(chips, coord.adjacency(dir))
+                .contains(&dir.opposite())
+    }
+
+    /// Follow the chips from `start` and return the directions and the end coord.
+    /// Go straight if possible.
+    fn trace(&self, chips: &Chips, start: Coord) -> (Vec<Direction>, Coord) {
+        let mut visited = vec![start];
+        let mut dirs: Vec<Direction> = vec![];
+        let mut current = start;
+
+        loop {
+            let next = DIRECTIONS
+                .into_iter()
+                .filter(|&d| {
+                    self.connected(chips, current, d) && !visited.contains(&current.adjacency(d))
+                })
+                .min_by_key(|d| Some(*d) != dirs.last().copied());
+            let Some(d) = next else {
+                break;
+            };
+            current = current.adjacency(d);
+            visited.push(current);
+            dirs.push(d);
+        }
+
+        (dirs, current)
+    }
+
+    /// Make text shapes from the rest of the grid.
+    ///
+    /// Runs of characters separated by two or more spaces become separate text shapes,
+    /// so that the blank area between them does not hide shapes behind.
+    fn take_texts(&self) -> Vec<(Coord, Box<dyn Shape>)> {
+        let mut texts: Vec<(Coord, Box<dyn Shape>)> = vec![];
+        let mut push = |x: usize, y: usize, s: String| {
+            texts.push((Coord::new(x as i16, y as i16), Box::new(Text::new(s))));
+        };
+
+        for (y, row) in self.0.iter().enumerate() {
+            // (x, text) of the run being read.
+            let mut run: Option<(usize, String)> = None;
+            let mut spaces = 0;
+
+            for (x, &c) in row.iter().enumerate() {
+                match c {
+                    WIDE_TAIL => continue,
+                    ' ' => {
+                        spaces += 1;
+                        continue;
+                    }
+                    _ => {}
+                }
+
+                match &mut run {
+                    Some((_, s)) if spaces < 2 => s.push_str(&" ".repeat(spaces)),
+                    _ => {
+                        if let Some((rx, s)) = run.take() {
+                            push(rx, y, s);
+                        }
+                        run = Some((x, String::new()));
+                    }
+                }
+                run.as_mut().unwrap().1.push(c);
+                spaces = 0;
+            }
+
+            if let Some((rx, s)) = run {
+                push(rx, y, s);
+            }
+        }
+
+        texts
+    }
+}
+
+/// Make shapes from a plain text, recognizing rects and paths drawn with the chips of `Style`.
+/// Coords are relative to the upper-left corner of the text.
+///
+/// The shapes are ordered as rects, paths and texts from back to front,
+/// since rects hide the area inside them.
+pub fn diagram(text: &str) -> Vec<(Coord, Box<dyn Shape>)> {
+    let mut grid = Grid::new(text);
+    let mut shapes = vec![];
+
+    for style in Style::ALL {
+        shapes.extend(grid.take_rects(style));
+    }
+    for style in Style::ALL {
+        shapes.extend(grid.take_paths(style));
+    }
+    shapes.extend(grid.take_texts());
+
+    shapes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+┌──┐
+│a │──▶ b
+└──┘
+ a-b  +--+
+╔═╗   |  |
+╚═╝   +--+
+";
+
+    fn encode(shapes: Vec<(Coord, Box<dyn Shape>)>) -> Vec<String> {
+        shapes
+            .iter()
+            .map(|(c, s)| format!("{} {} {}", c.x, c.y, s.encode()))
+            .collect()
+    }
+
+    #[test]
+    fn take_rects() {
+        let mut grid = Grid::new(TEXT);
+        assert_eq!(
+            encode(grid.take_rects(Style::Single)),
+            ["0 0 rect 4 3 single"]
+        );
+        assert_eq!(
+            encode(grid.take_rects(Style::Double)),
+            ["0 4 rect 3 2 double"]
+        );
+        assert_eq!(
+            encode(grid.take_rects(Style::Ascii)),
+            ["6 3 rect 4 3 ascii"]
+        );
+        // Taken rects are cleared from the grid.
+        assert!(grid.take_rects(Style::Single).is_empty());
+    }
+
+    #[test]
+    fn take_paths() {
+        let mut grid = Grid::new(TEXT);
+        for style in Style::ALL {
+            grid.take_rects(style);
+        }
+        assert_eq!(
+            encode(grid.take_paths(Style::Single)),
+            ["4 1 path single > ll"]
+        );
+        // `a-b` is too short for an ascii path.
+        assert!(grid.take_paths(Style::Ascii).is_empty());
+    }
+
+    #[test]
+    fn take_texts_of_the_rest() {
+        let shapes = encode(diagram(TEXT));
+        assert_eq!(shapes.len(), 7);
+        assert_eq!(shapes[4..], ["1 1 text a", "8 1 text b", "1 3 text a-b"]);
+    }
+}
@@ -0,0 +1,76 @@
+//! Import of CSV files as tables.
+//!
+//! Fields are separated by `,` and records by line breaks.
+//! Fields can be quoted by `"` to contain `,`, `"` (as `""`) and line breaks,
+//! which are taken as spaces in the table.
+
+use crate::app::shape::{style::Style, table::Table};
+use anyhow::{bail, ensure};
+
+/// Split the CSV into records of fields.
+fn records(src: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        bail!("unterminated quote");
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Make a table of the CSV.
+pub fn table(src: &str, style: Style) -> anyhow::Result<Table> {
+    let records = records(src)?;
+    ensure!(!records.is_empty(), "empty CSV");
+    Ok(Table::new(records, style))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_with_quotes() {
+        let src = "a,\"b,c\"\r\n\"say \"\"hi\"\"\",\"x\ny\"\n,\n";
+        assert_eq!(
+            records(src).unwrap(),
+            [vec!["a", "b,c"], vec!["say \"hi\"", "x\ny"], vec!["", ""]]
+        );
+    }
+
+    #[test]
+    fn last_record_without_line_break() {
+        assert_eq!(records("a,b").unwrap(), [vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(table("a,\"b\n", Style::Single).is_err());
+        assert!(table("", Style::Single).is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! Import of Graphviz DOT.
+//!
+//! Supported subset:
+//! - `graph` and `digraph`, with `strict` and the name ignored.
+//! - Node statements with `label`, `style` (`dashed`, `dotted`, `bold`) and `shape`
+//!   (`doublecircle` and `doubleoctagon` are drawn with the double style) attributes.
+//! - Edge statements with `label`, including chains (`a -> b -> c`) and groups (`a -> {b c}`).
+//! - `rankdir` given as `rankdir=LR` or `graph [rankdir=LR]`.
+//! - Default attributes of `node [...]`, and subgraphs, whose nodes are flattened.
+//!
+//! Other attributes and ports are ignored. Edges from a node to itself (`a -> a`) are not
+//! drawn, and their number is reported by the import.
+
+use super::graph::{Diagram, Edge, Flow, Graph};
+use crate::app::shape::style::Style;
+use anyhow::{bail, Context};
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Token {
+    /// Identifier, number or string.
+    Id(String),
+    /// `->` or `--`.
+    EdgeOp,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Eq,
+    Semi,
+    Comma,
+    Colon,
+}
+
+fn tokenize(src: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '=' => Token::Eq,
+            ';' => Token::Semi,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            // Comments and preprocessor lines.
+            '#' => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+                continue;
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                continue;
+            }
+            '-' if matches!(chars.peek(), Some('>' | '-')) => {
+                chars.next();
+                Token::EdgeOp
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n' | 'l' | 'r') => s.push('\n'),
+                            Some('N') => s.push_str("\\N"),
+                            Some('\n') => {}
+                            Some(c) => s.push(c),
+                            None => bail!("unterminated string"),
+                        },
+                        Some(c) => s.push(c),
+                        None => bail!("unterminated string"),
+                    }
+                }
+                // Trailing line break of `\l` and `\r` justified labels.
+                Token::Id(s.trim_end_matches('\n').to_string())
+            }
+            '<' => {
+                // HTML string, taken as is.
+                let mut s = String::new();
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    match c {
+                        '<' => depth += 1,
+                        '>' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    s.push(c);
+                }
+                Token::Id(s)
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+                Token::Id(s)
+            }
+            c => bail!("unexpected `{}`", c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Attributes applied to nodes.
+#[derive(Clone, Default)]
+struct NodeAttrs {
+    label: Option<String>,
+    style: Option<Style>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    graph: Graph,
+    node_defaults: NodeAttrs,
+    directed: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> anyhow::Result<()> {
+        match self.next() {
+            Some(t) if t == *token => Ok(()),
+            Some(t) => bail!("expected {:?}, found {:?}", token, t),
+            None => bail!("expected {:?}, found end of file", token),
+        }
+    }
+
+    fn id(&mut self) -> anyhow::Result<String> {
+        match self.next() {
+            Some(Token::Id(id)) => Ok(id),
+            Some(t) => bail!("expected identifier, found {:?}", t),
+            None => bail!("expected identifier, found end of file"),
+        }
+    }
+
+    /// `[strict] (graph|digraph) [ID] '{' stmt_list '}'`
+    fn parse_graph(&mut self) -> anyhow::Result<()> {
+        let mut keyword = self.id()?;
+        if keyword.eq_ignore_ascii_case("strict") {
+            keyword = self.id()?;
+        }
+        self.directed = match keyword.to_ascii_lowercase().as_str() {
+            "digraph" => true,
+            "graph" => false,
+            _ => bail!("expected `graph` or `digraph`, found `{}`", keyword),
+        };
+        if matches!(self.peek(), Some(Token::Id(_))) {
+            self.next();
+        }
+        self.expect(&Token::LBrace)?;
+        self.parse_stmt_list()?;
+        Ok(())
+    }
+
+    /// Parse statements until `}`, and return the nodes appearing in them.
+    fn parse_stmt_list(&mut self) -> anyhow::Result<Vec<usize>> {
+        let mut nodes = vec![];
+        loop {
+            match self.peek() {
+                Some(Token::RBrace) => {
+                    self.next();
+                    return Ok(nodes);
+                }
+                Some(Token::Semi | Token::Comma) => {
+                    self.next();
+                }
+                Some(_) => nodes.extend(self.parse_stmt()?),
+                None => bail!("expected `}}`, found end of file"),
+            }
+        }
+    }
+
+    fn parse_stmt(&mut self) -> anyhow::Result<Vec<usize>> {
+        if let Some(Token::Id(id)) = self.peek() {
+            match id.to_ascii_lowercase().as_str() {
+                "graph" => {
+                    self.next();
+                    for (key, value) in self.parse_attr_list()? {
+                        self.set_graph_attr(&key, &value);
+                    }
+                    return Ok(vec![]);
+                }
+                "node" => {
+                    self.next();
+                    let attrs = self.parse_attr_list()?;
+                    self.node_defaults = Self::node_attrs(self.node_defaults.clone(), &attrs);
+                    return Ok(vec![]);
+                }
+                "edge" => {
+                    self.next();
+                    self.parse_attr_list()?;
+                    return Ok(vec![]);
+                }
+                _ => {}
+            }
+            if self.tokens.get(self.pos + 1) == Some(&Token::Eq) {
+                let key = self.id()?;
+                self.next();
+                let value = self.id()?;
+                self.set_graph_attr(&key, &value);
+                return Ok(vec![]);
+            }
+        }
+
+        // Node or edge statement.
+        let mut operands = vec![self.parse_operand()?];
+        while self.eat(&Token::EdgeOp) {
+            operands.push(self.parse_operand()?);
+        }
+        let attrs = self.parse_attr_list()?;
+
+        if let [nodes] = operands.as_slice() {
+            // Node statement.
+            if let [node] = nodes.as_slice() {
+                self.apply_node_attrs(*node, &attrs);
+            }
+            return Ok(nodes.clone());
+        }
+
+        let label = attrs
+            .iter()
+            .rev()
+            .find(|(k, _)| k == "label")
+            .map(|(_, v)| v.clone());
+        for pair in operands.windows(2) {
+            for &from in &pair[0] {
+                for &to in &pair[1] {
+                    self.graph.edges.push(Edge {
+                        from,
+                        to,
+                        label: label.clone(),
+                        has_arrow: self.directed,
+                        style: Style::Single,
+                    });
+                }
+            }
+        }
+        Ok(operands.concat())
+    }
+
+    /// Node id (with an optional port) or subgraph, returning the nodes in it.
+    fn parse_operand(&mut self) -> anyhow::Result<Vec<usize>> {
+        match self.peek() {
+            Some(Token::LBrace) => {
+                self.next();
+                self.parse_stmt_list()
+            }
+            Some(Token::Id(id)) if id.eq_ignore_ascii_case("subgraph") => {
+                self.next();
+                if matches!(self.peek(), Some(Token::Id(_))) {
+                    self.next();
+                }
+                self.expect(&Token::LBrace)?;
+                self.parse_stmt_list()
+            }
+            _ => {
+                let id = self.id()?;
+                // Port and compass point.
+                while self.eat(&Token::Colon) {
+                    self.id()?;
+                }
+                Ok(vec![self.add_node(&id)])
+            }
+        }
+    }
+
+    /// `('[' (ID ['=' ID] [';'|','])* ']')*`
+    fn parse_attr_list(&mut self) -> anyhow::Result<Vec<(String, String)>> {
+        let mut attrs = vec![];
+        while self.eat(&Token::LBracket) {
+            while !self.eat(&Token::RBracket) {
+                if self.eat(&Token::Semi) || self.eat(&Token::Comma) {
+                    continue;
+                }
+                let key = self.id()?;
+                let value = if self.eat(&Token::Eq) {
+                    self.id()?
+                } else {
+                    "true".to_string()
+                };
+                attrs.push((key, value));
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn set_graph_attr(&mut self, key: &str, value: &str) {
+        if key == "rankdir" {
+            self.graph.flow = match value.to_ascii_uppercase().as_str() {
+                "LR" => Flow::LeftRight,
+                "RL" => Flow::RightLeft,
+                "BT" => Flow::BottomUp,
+                _ => Flow::TopDown,
+            };
+        }
+    }
+
+    fn node_attrs(mut attrs: NodeAttrs, list: &[(String, String)]) -> NodeAttrs {
+        for (key, value) in list {
+            match key.as_str() {
+                "label" => attrs.label = Some(value.clone()),
+                "style" => {
+                    attrs.style = match value.as_str() {
+                        "dashed" => Some(Style::Dash),
+                        "dotted" => Some(Style::Dot),
+                        "bold" => Some(Style::SingleBold),
+                        _ => attrs.style,
+                    }
+                }
+                "shape" if value.starts_with("double") => attrs.style = Some(Style::Double),
+                _ => {}
+            }
+        }
+        attrs
+    }
+
+    /// Node with the id, made with the default attributes if it is new.
+    fn add_node(&mut self, id: &str) -> usize {
+        let num_nodes = self.graph.nodes.len();
+        let node = self.graph.node(id);
+        if node == num_nodes {
+            let defaults = self.node_defaults.clone();
+            self.set_node_attrs(node, defaults);
+        }
+        node
+    }
+
+    fn apply_node_attrs(&mut self, node: usize, list: &[(String, String)]) {
+        let attrs = Self::node_attrs(NodeAttrs::default(), list);
+        self.set_node_attrs(node, attrs);
+    }
+
+    fn set_node_attrs(&mut self, node: usize, attrs: NodeAttrs) {
+        let node = &mut self.graph.nodes[node];
+        if let Some(label) = attrs.label {
+            // `\N` is the name of the node.
+            node.label = label.replace("\\N", &node.id);
+        }
+        if let Some(style) = attrs.style {
+            node.style = style;
+        }
+    }
+}
+
+/// Make shapes from a DOT graph, laid out on the grid.
+/// Edges from a node to itself are not drawn, and are counted in the diagram.
+pub fn diagram(src: &str) -> anyhow::Result<Diagram> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        graph: Graph::default(),
+        node_defaults: NodeAttrs::default(),
+        directed: true,
+    };
+    parser.parse_graph().context("malformed DOT")?;
+    Ok(super::graph::layout(&parser.graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::canvas::Canvas;
+
+    fn id(s: &str) -> Token {
+        Token::Id(s.to_string())
+    }
+
+    #[test]
+    fn tokenize_statements() {
+        let src = "# pre\ndigraph { a -> \"b \\\"c\\\"\" [label=x]; // line\n /* block */ }";
+        assert_eq!(
+            tokenize(src).unwrap(),
+            [
+                id("digraph"),
+                Token::LBrace,
+                id("a"),
+                Token::EdgeOp,
+                id("b \"c\""),
+                Token::LBracket,
+                id("label"),
+                Token::Eq,
+                id("x"),
+                Token::RBracket,
+                Token::Semi,
+                Token::RBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn diagram_has_nodes_and_edges() {
+        let shapes = diagram("digraph { a -> b -> c; a -> c [label=x] }")
+            .unwrap()
+            .shapes;
+        let canvas: Canvas = shapes.into_iter().collect();
+        let text = canvas.to_plain_text();
+        for s in ["a", "b", "c", "x", "▼"] {
+            assert!(text.contains(s), "{} is not in\n{}", s, text);
+        }
+    }
+
+    #[test]
+    fn count_self_loops() {
+        let diagram = diagram("digraph { a -> a; a -> b; b -> b }").unwrap();
+        assert_eq!(diagram.num_self_loops, 2);
+        let canvas: Canvas = diagram.shapes.into_iter().collect();
+        let text = canvas.to_plain_text();
+        for s in ["a", "b", "▼"] {
+            assert!(text.contains(s), "{} is not in\n{}", s, text);
+        }
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(diagram("digraph { a -> }").is_err());
+        assert!(diagram("digraph { \"a }").is_err());
+    }
+}
@@ -0,0 +1,582 @@
+//! Layered layout of graphs, used by the importers of graph languages.
+//!
+//! Nodes are ranked by the longest path from the sources, and the ranks are stacked along
+//! the flow. An edge is split into links between adjacent ranks, passing a point in each rank
+//! between its ends, and edges going backward are laid out reversed. Each link has its own
+//! ports on the boxes and its own row to turn in the gap, so that links do not overlap.
+
+use crate::app::shape::{path::Path, rect::Rect, style::Style, text::Text, Shape};
+use crate::util::{Coord, Direction};
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+
+/// Minimum gap between ranks along the flow: the exit, turning and entry cells of links.
+const RANK_GAP: i16 = 3;
+/// Gap between nodes in a rank.
+const NODE_GAP: i16 = 2;
+
+/// Direction in which edges flow.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Flow {
+    #[default]
+    TopDown,
+    BottomUp,
+    LeftRight,
+    RightLeft,
+}
+
+impl Flow {
+    fn is_vertical(&self) -> bool {
+        matches!(self, Flow::TopDown | Flow::BottomUp)
+    }
+
+    fn is_reversed(&self) -> bool {
+        matches!(self, Flow::BottomUp | Flow::RightLeft)
+    }
+}
+
+pub struct Node {
+    pub id: String,
+    pub label: String,
+    pub style: Style,
+}
+
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub label: Option<String>,
+    pub has_arrow: bool,
+    pub style: Style,
+}
+
+#[derive(Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub flow: Flow,
+    ids: HashMap<String, usize>,
+}
+
+impl Graph {
+    /// Index of the node with the id.
+    /// A node labelled with the id is added if there is no such node.
+    pub fn node(&mut self, id: &str) -> usize {
+        if let Some(&i) = self.ids.get(id) {
+            return i;
+        }
+        self.nodes.push(Node {
+            id: id.to_string(),
+            label: id.to_string(),
+            style: Style::Single,
+        });
+        self.ids.insert(id.to_string(), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+}
+
+/// Size of the label.
+fn text_size(label: &str) -> (i16, i16) {
+    let width = label.lines().map(UnicodeWidthStr::width).max().unwrap_or(0);
+    let height = label.lines().count().max(1);
+    (width as i16, height as i16)
+}
+
+/// Size of the box of the label, with the border and a padding space on each side.
+fn box_size(label: &str) -> (i16, i16) {
+    let (width, height) = text_size(label);
+    (width + 4, height + 2)
+}
+
+/// Graph laid out into shapes.
+pub struct Diagram {
+    /// Shapes ordered from back to front, with coords relative to the upper-left corner of
+    /// the layout.
+    pub shapes: Vec<(Coord, Box<dyn Shape>)>,
+    /// Number of edges from a node to itself, which are not drawn.
+    pub num_self_loops: usize,
+}
+
+/// Box of a node, or a point where an edge passes a rank, in the abstract coords
+/// where `m` goes along the flow and `c` across it.
+#[derive(Clone, Copy, Default)]
+struct NodeBox {
+    m: i16,
+    c: i16,
+    main: i16,
+    cross: i16,
+}
+
+/// Direction in the abstract coords.
+#[derive(Clone, Copy)]
+enum Dir {
+    MainPlus,
+    CrossPlus,
+    CrossMinus,
+}
+
+/// Part of an edge between items of adjacent ranks.
+/// Items are the nodes followed by the points where edges pass ranks.
+#[derive(Default)]
+struct Link {
+    from: usize,
+    to: usize,
+    /// Cross coord leaving `from`.
+    exit: i16,
+    /// Cross coord entering `to`.
+    entry: i16,
+    /// Row to turn, counted from the end of the rank of `from`.
+    turn: i16,
+    /// Size of the label of the edge, put beside the first link.
+    label: Option<(i16, i16)>,
+}
+
+/// Rank of each node, and whether each edge goes backward.
+/// Backward edges are the ones closing cycles found by DFS, and are ignored in ranking.
+fn rank(graph: &Graph) -> (Vec<usize>, Vec<bool>) {
+    let n = graph.nodes.len();
+    let mut out_edges = vec![vec![]; n];
+    for (i, e) in graph.edges.iter().enumerate() {
+        out_edges[e.from].push(i);
+    }
+
+    // 0: unvisited, 1: on the stack, 2: done.
+    let mut state = vec![0u8; n];
+    let mut backward = vec![false; graph.edges.len()];
+    for root in 0..n {
+        if state[root] != 0 {
+            continue;
+        }
+        // (node, index of the next out edge to visit)
+        let mut stack = vec![(root, 0)];
+        state[root] = 1;
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&e) = out_edges[node].get(*next) else {
+                state[node] = 2;
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            let to = graph.edges[e].to;
+            match state[to] {
+                0 => {
+                    state[to] = 1;
+                    stack.push((to, 0));
+                }
+                1 => backward[e] = true,
+                _ => {}
+            }
+        }
+    }
+
+    // Longest path in the DAG, in a topological order.
+    let mut in_degree = vec![0; n];
+    for (e, edge) in graph.edges.iter().enumerate() {
+        if !backward[e] {
+            in_degree[edge.to] += 1;
+        }
+    }
+    let mut ranks = vec![0; n];
+    let mut queue: Vec<usize> = (0..n).filter(|&i| in_degree[i] == 0).collect();
+    while let Some(node) = queue.pop() {
+        for &e in &out_edges[node] {
+            if backward[e] {
+                continue;
+            }
+            let to = graph.edges[e].to;
+            ranks[to] = ranks[to].max(ranks[node] + 1);
+            in_degree[to] -= 1;
+            if in_degree[to] == 0 {
+                queue.push(to);
+            }
+        }
+    }
+
+    (ranks, backward)
+}
+
+/// Items of each rank, ordered by the mean position of their predecessors
+/// to reduce the crossings of links.
+fn order(item_ranks: &[usize], links: &[Link]) -> Vec<Vec<usize>> {
+    let num_ranks = item_ranks.iter().max().map_or(0, |r| r + 1);
+    let mut layers = vec![vec![]; num_ranks];
+    for (item, &r) in item_ranks.iter().enumerate() {
+        layers[r].push(item);
+    }
+
+    let mut position = vec![0.0; item_ranks.len()];
+    for layer in &mut layers {
+        let key = |item: usize, i: usize| {
+            let preds: Vec<f64> = links
+                .iter()
+                .filter(|l| l.to == item)
+                .map(|l| position[l.from])
+                .collect();
+            if preds.is_empty() {
+                i as f64
+            } else {
+                preds.iter().sum::<f64>() / preds.len() as f64
+            }
+        };
+        let mut keyed: Vec<(f64, usize)> = layer
+            .iter()
+            .enumerate()
+            .map(|(i, &item)| (key(item, i), item))
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        *layer = keyed.into_iter().map(|(_, item)| item).collect();
+        for (i, &item) in layer.iter().enumerate() {
+            position[item] = i as f64;
+        }
+    }
+    layers
+}
+
+/// Offsets of ports packed on the side of a box, and the cross size of the box they need.
+/// A port with a label has room for it across the flow, after the port in vertical flows
+/// and before it in horizontal flows.
+fn pack_ports(labels: &[Option<(i16, i16)>], flow: Flow) -> (Vec<i16>, i16) {
+    let mut offsets = vec![];
+    if flow.is_vertical() {
+        let mut offset = 1;
+        for label in labels {
+            offsets.push(offset);
+            offset += label.map_or(2, |(w, _)| w + 3);
+        }
+        (offsets, offset)
+    } else {
+        let mut offset = 0;
+        for label in labels {
+            offset += label.map_or(1, |(_, h)| h + 1);
+            offsets.push(offset);
+        }
+        (offsets, offset + 2)
+    }
+}
+
+/// Cross coords of ports on the side of the box, given the labels of their links.
+/// Ports are spread over the side except its corners, or packed at the center if they have
+/// labels.
+fn ports(b: &NodeBox, labels: &[Option<(i16, i16)>], flow: Flow) -> Vec<i16> {
+    let n = labels.len();
+    if b.cross < 3 {
+        return vec![b.c; n];
+    }
+    if labels.iter().any(Option::is_some) {
+        let (offsets, need) = pack_ports(labels, flow);
+        let shift = (b.cross - need) / 2;
+        return offsets.into_iter().map(|o| b.c + shift + o).collect();
+    }
+    let side = b.cross as usize - 2;
+    (0..n)
+        .map(|i| b.c + 1 + ((2 * i + 1) * side / (2 * n)) as i16)
+        .collect()
+}
+
+/// Order in which the links in a gap turn, given as `(exit, entry)`.
+/// A link turns after the links leaving the column which it enters, so that they do not
+/// overlap. Links going to the same side turn from the outer one to reduce crossings.
+fn turn_order(links: &[(i16, i16)]) -> Vec<usize> {
+    let mut rest: Vec<usize> = (0..links.len()).collect();
+    rest.sort_by_key(|&i| {
+        let (exit, entry) = links[i];
+        if entry > exit {
+            (0, -exit)
+        } else {
+            (1, exit)
+        }
+    });
+    let mut order = vec![];
+    while !rest.is_empty() {
+        // Links crossing each other's column cannot avoid overlapping, so take the first one.
+        let next = rest
+            .iter()
+            .position(|&i| !rest.iter().any(|&j| j != i && links[j].0 == links[i].1))
+            .unwrap_or(0);
+        order.push(rest.remove(next));
+    }
+    order
+}
+
+/// Lay out the graph into shapes.
+/// Edges from a node to itself are left out, and counted in the diagram.
+pub fn layout(graph: &Graph) -> Diagram {
+    let flow = graph.flow;
+    let (ranks, backward) = rank(graph);
+
+    // Split edges into links, adding a point in each rank between the ends.
+    let mut item_ranks = ranks.clone();
+    let mut links: Vec<Link> = vec![];
+    let mut chains = vec![];
+    let mut num_self_loops = 0;
+    for (e, edge) in graph.edges.iter().enumerate() {
+        if edge.from == edge.to {
+            num_self_loops += 1;
+            continue;
+        }
+        let (from, to) = if backward[e] {
+            (edge.to, edge.from)
+        } else {
+            (edge.from, edge.to)
+        };
+        let mut chain = vec![];
+        let mut item = from;
+        let mut label = edge.label.as_deref().map(text_size);
+        for r in ranks[from] + 1..=ranks[to] {
+            let next = if r == ranks[to] {
+                to
+            } else {
+                item_ranks.push(r);
+                item_ranks.len() - 1
+            };
+            chain.push(links.len());
+            links.push(Link {
+                from: item,
+                to: next,
+                label: label.take(),
+                ..Default::default()
+            });
+            item = next;
+        }
+        chains.push((edge, backward[e], chain));
+    }
+    let layers = order(&item_ranks, &links);
+
+    // Size boxes, widened to give each link its own port and room for labels.
+    let mut boxes: Vec<NodeBox> = (0..item_ranks.len())
+        .map(|item| {
+            let Some(node) = graph.nodes.get(item) else {
+                return NodeBox {
+                    cross: 1,
+                    ..Default::default()
+                };
+            };
+            let (w, h) = box_size(&node.label);
+            let (main, cross) = if flow.is_vertical() { (h, w) } else { (w, h) };
+            let outs: Vec<_> = links
+                .iter()
+                .filter(|l| l.from == item)
+                .map(|l| l.label)
+                .collect();
+            let num_in = links.iter().filter(|l| l.to == item).count();
+            let (_, out_cross) = pack_ports(&outs, flow);
+            let (_, in_cross) = pack_ports(&vec![None; num_in], flow);
+            NodeBox {
+                main,
+                cross: cross.max(out_cross).max(in_cross),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    // Place items across the flow.
+    let cross_lens: Vec<i16> = layers
+        .iter()
+        .map(|layer| {
+            layer
+                .iter()
+                .map(|&n| boxes[n].cross + NODE_GAP)
+                .sum::<i16>()
+                - NODE_GAP
+        })
+        .collect();
+    let max_cross_len = cross_lens.iter().copied().max().unwrap_or(0);
+    for (layer, cross_len) in layers.iter().zip(cross_lens) {
+        // Center the rank.
+        let mut c = (max_cross_len - cross_len) / 2;
+        for &item in layer {
+            boxes[item].c = c;
+            c += boxes[item].cross + NODE_GAP;
+        }
+    }
+
+    // Assign ports ordered by the other ends of links to reduce crossings.
+    let center = |b: &NodeBox| b.c + b.cross / 2;
+    for (item, b) in boxes.iter().enumerate() {
+        let mut outs: Vec<usize> = (0..links.len())
+            .filter(|&l| links[l].from == item)
+            .collect();
+        outs.sort_by_key(|&l| center(&boxes[links[l].to]));
+        let labels: Vec<_> = outs.iter().map(|&l| links[l].label).collect();
+        for (&l, port) in outs.iter().zip(ports(b, &labels, flow)) {
+            links[l].exit = port;
+        }
+        let mut ins: Vec<usize> = (0..links.len()).filter(|&l| links[l].to == item).collect();
+        ins.sort_by_key(|&l| center(&boxes[links[l].from]));
+        for (&l, port) in ins.iter().zip(ports(b, &vec![None; ins.len()], flow)) {
+            links[l].entry = port;
+        }
+    }
+
+    // Labels are put beside the exits of links, which are lengthened along the flow for them.
+    let (label_width, label_height) = links
+        .iter()
+        .filter_map(|l| l.label)
+        .fold((0, 0), |(w, h), (lw, lh)| (w.max(lw), h.max(lh)));
+    let exit_len = if flow.is_vertical() {
+        label_height.max(1)
+    } else {
+        label_width + 2
+    };
+
+    // Give each turning link its own row in the gap after its rank.
+    let mut gaps = vec![RANK_GAP; layers.len()];
+    for (r, gap) in gaps.iter_mut().enumerate() {
+        let turning: Vec<usize> = (0..links.len())
+            .filter(|&l| item_ranks[links[l].from] == r && links[l].exit != links[l].entry)
+            .collect();
+        let ends: Vec<(i16, i16)> = turning
+            .iter()
+            .map(|&l| (links[l].exit, links[l].entry))
+            .collect();
+        for (i, j) in turn_order(&ends).into_iter().enumerate() {
+            links[turning[j]].turn = i as i16 + 1;
+        }
+        *gap = (*gap).max(exit_len + turning.len() as i16 + 1);
+    }
+
+    // Stack ranks along the flow.
+    let mut rank_ends = vec![];
+    let mut m = 0;
+    for (layer, gap) in layers.iter().zip(gaps) {
+        for &item in layer {
+            boxes[item].m = m;
+        }
+        m += layer.iter().map(|&n| boxes[n].main).max().unwrap_or(0);
+        rank_ends.push(m);
+        m += gap;
+    }
+
+    // Route edges through the points of their links as (start, dirs, end), and put labels
+    // in the room beside the exits as the opposite corners.
+    let mut routes = vec![];
+    for (edge, is_backward, chain) in chains {
+        let (first, last) = (&links[chain[0]], &links[chain[chain.len() - 1]]);
+        let (src, dst) = (boxes[first.from], boxes[last.to]);
+        let mut points = vec![(src.m + src.main, first.exit)];
+        for link in chain.iter().map(|&l| &links[l]) {
+            if link.exit != link.entry {
+                let turn = rank_ends[item_ranks[link.from]] + exit_len - 1 + link.turn;
+                points.push((turn, link.exit));
+                points.push((turn, link.entry));
+            }
+        }
+        points.push((dst.m - 1, last.entry));
+
+        let mut dirs = vec![];
+        for pair in points.windows(2) {
+            let ((m0, c0), (m1, c1)) = (pair[0], pair[1]);
+            let (dir, len) = if c1 > c0 {
+                (Dir::CrossPlus, c1 - c0)
+            } else if c1 < c0 {
+                (Dir::CrossMinus, c0 - c1)
+            } else {
+                (Dir::MainPlus, m1 - m0)
+            };
+            dirs.extend(std::iter::repeat_n(dir, len as usize));
+        }
+
+        let rank_end = rank_ends[item_ranks[first.from]];
+        let label = first.label.map(|(w, h)| {
+            if flow.is_vertical() {
+                (
+                    (rank_end, first.exit + 2),
+                    (rank_end + h - 1, first.exit + w + 1),
+                )
+            } else {
+                (
+                    (rank_end + 1, first.exit - h),
+                    (rank_end + w, first.exit - 1),
+                )
+            }
+        });
+        routes.push((
+            edge,
+            is_backward,
+            points[0],
+            dirs,
+            points[points.len() - 1],
+            label,
+        ));
+    }
+
+    // Map the abstract coords to the grid.
+    let max_m = boxes.iter().map(|b| b.m + b.main - 1).max().unwrap_or(0);
+    let grid = |m: i16, c: i16| {
+        let m = if flow.is_reversed() { max_m - m } else { m };
+        if flow.is_vertical() {
+            Coord::new(c, m)
+        } else {
+            Coord::new(m, c)
+        }
+    };
+    let direction = |dir: Dir| match (dir, flow) {
+        (Dir::MainPlus, Flow::TopDown) => Direction::Down,
+        (Dir::MainPlus, Flow::BottomUp) => Direction::Up,
+        (Dir::MainPlus, Flow::LeftRight) => Direction::Right,
+        (Dir::MainPlus, Flow::RightLeft) => Direction::Left,
+        (Dir::CrossPlus, _) if flow.is_vertical() => Direction::Right,
+        (Dir::CrossMinus, _) if flow.is_vertical() => Direction::Left,
+        (Dir::CrossPlus, _) => Direction::Down,
+        (Dir::CrossMinus, _) => Direction::Up,
+    };
+
+    let mut shapes: Vec<(Coord, Box<dyn Shape>)> = vec![];
+    let mut labels: Vec<(Coord, Box<dyn Shape>)> = vec![];
+    // Upper-left corner on the grid of the area between the opposite corners.
+    let upper_left = |(m0, c0), (m1, c1)| {
+        let (a, b) = (grid(m0, c0), grid(m1, c1));
+        Coord::new(a.x.min(b.x), a.y.min(b.y))
+    };
+    for (node, b) in graph.nodes.iter().zip(&boxes) {
+        let upper_left = upper_left((b.m, b.c), (b.m + b.main - 1, b.c + b.cross - 1));
+        let (w, h) = if flow.is_vertical() {
+            (b.cross, b.main)
+        } else {
+            (b.main, b.cross)
+        };
+        let rect = Rect::new(crate::util::Size::new(w as u16, h as u16), node.style);
+        shapes.push((upper_left, Box::new(rect)));
+        if !node.label.is_empty() {
+            // Center the label in the box, which may be widened for ports.
+            let (label_w, label_h) = box_size(&node.label);
+            let offset = Coord::new(2 + (w - label_w) / 2, 1 + (h - label_h) / 2);
+            let text = Text::new(node.label.clone());
+            labels.push((upper_left + offset, Box::new(text)));
+        }
+    }
+    shapes.append(&mut labels);
+
+    // Draw the paths of backward edges from the end of their routes.
+    for (edge, is_backward, (m0, c0), dirs, (m1, c1), label) in routes {
+        if let (Some(text), Some((corner, opposite))) = (&edge.label, label) {
+            let text = Text::new(text.clone());
+            labels.push((upper_left(corner, opposite), Box::new(text)));
+        }
+        let dirs: Vec<Direction> = dirs.into_iter().map(direction).collect();
+        let (start, dirs) = if is_backward {
+            let reversed = dirs.iter().rev().map(Direction::opposite).collect();
+            (grid(m1, c1), reversed)
+        } else {
+            (grid(m0, c0), dirs)
+        };
+        if dirs.is_empty() {
+            continue;
+        }
+        let path = Path::new(dirs, false, edge.has_arrow, edge.style);
+        shapes.push((start + path.start_to_upper_left(), Box::new(path)));
+    }
+    shapes.append(&mut labels);
+
+    // Keep coords non-negative.
+    let min_x = shapes.iter().map(|(c, _)| c.x).min().unwrap_or(0).min(0);
+    let min_y = shapes.iter().map(|(c, _)| c.y).min().unwrap_or(0).min(0);
+    let shapes = shapes
+        .into_iter()
+        .map(|(c, s)| (c - Coord::new(min_x, min_y), s))
+        .collect();
+    Diagram {
+        shapes,
+        num_self_loops,
+    }
+}
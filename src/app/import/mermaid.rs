@@ -0,0 +1,336 @@
+//! Import of Mermaid flowcharts.
+//!
+//! Supported subset:
+//! - `flowchart` or `graph` header with the direction `TD`, `TB`, `BT`, `LR` or `RL`.
+//! - Nodes `id`, `id[text]` (single), `id(text)` (bold) and `id{text}` (double).
+//!   Doubled brackets such as `id((text))` are taken as the outer one, and `<br>` breaks lines.
+//! - Links `-->`, `---`, `-.->` (dot), `==>` (bold) and their longer forms, labelled as
+//!   `-->|text|` or `-- text -->`, chained (`a --> b --> c`) and grouped (`a & b --> c`).
+//!
+//! Subgraphs are flattened, and styling statements are ignored. Links from a node to itself
+//! (`a --> a`) are not drawn, and their number is reported by the import.
+
+use super::graph::{Diagram, Edge, Flow, Graph};
+use crate::app::shape::style::Style;
+use anyhow::{bail, Context};
+
+/// Chars of links.
+const LINK_CHARS: &[char] = &['-', '.', '=', '>', '<'];
+
+/// Statements which are ignored.
+const IGNORED: &[&str] = &[
+    "subgraph",
+    "end",
+    "direction",
+    "classDef",
+    "class",
+    "style",
+    "linkStyle",
+    "click",
+];
+
+struct Link {
+    label: Option<String>,
+    has_arrow: bool,
+    style: Style,
+}
+
+/// Text in a node or a label, with the quotes removed and `<br>` as line breaks.
+fn text(s: &str) -> String {
+    let s = s.trim();
+    let s = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s);
+    s.replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    graph: &'a mut Graph,
+}
+
+impl Parser<'_> {
+    fn skip_spaces(&mut self) {
+        self.s = self.s.trim_start();
+    }
+
+    /// `node ('&' node)*`
+    fn parse_group(&mut self) -> anyhow::Result<Vec<usize>> {
+        let mut nodes = vec![self.parse_node()?];
+        loop {
+            self.skip_spaces();
+            let Some(rest) = self.s.strip_prefix('&') else {
+                return Ok(nodes);
+            };
+            self.s = rest;
+            nodes.push(self.parse_node()?);
+        }
+    }
+
+    /// `id [shape]`
+    fn parse_node(&mut self) -> anyhow::Result<usize> {
+        self.skip_spaces();
+        let end = self
+            .s
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.s.len());
+        if end == 0 {
+            bail!("expected node at `{}`", self.s);
+        }
+        let (id, rest) = self.s.split_at(end);
+        self.s = rest;
+        let node = self.graph.node(id);
+
+        let style = match self.s.chars().next() {
+            Some('[') => Style::Single,
+            Some('(') => Style::SingleBold,
+            Some('{') => Style::Double,
+            _ => return Ok(self.skip_class(node)),
+        };
+
+        // Closing brackets in the reverse order of the opening ones.
+        let open_len = self
+            .s
+            .find(|c: char| !"[({".contains(c))
+            .unwrap_or(self.s.len());
+        let close: String = self.s[..open_len]
+            .chars()
+            .rev()
+            .map(|c| match c {
+                '[' => ']',
+                '(' => ')',
+                _ => '}',
+            })
+            .collect();
+        let rest = &self.s[open_len..];
+
+        // Find the closing brackets outside quotes.
+        let mut in_quote = false;
+        let mut close_at = None;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '"' => in_quote = !in_quote,
+                _ if !in_quote && rest[i..].starts_with(&close) => {
+                    close_at = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let Some(close_at) = close_at else {
+            bail!("expected `{}` for node `{}`", close, id);
+        };
+
+        let node_ref = &mut self.graph.nodes[node];
+        node_ref.label = text(&rest[..close_at]);
+        node_ref.style = style;
+        self.s = &rest[close_at + close.len()..];
+        Ok(self.skip_class(node))
+    }
+
+    /// Skip `:::class` after a node.
+    fn skip_class(&mut self, node: usize) -> usize {
+        if let Some(rest) = self.s.strip_prefix(":::") {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            self.s = &rest[end..];
+        }
+        node
+    }
+
+    /// Take a run of link chars.
+    fn take_link_run(&mut self) -> &str {
+        let end = self
+            .s
+            .find(|c: char| !LINK_CHARS.contains(&c))
+            .unwrap_or(self.s.len());
+        let (run, rest) = self.s.split_at(end);
+        self.s = rest;
+        run
+    }
+
+    /// Link with an optional label, or `None` if there is no link.
+    fn parse_link(&mut self) -> anyhow::Result<Option<Link>> {
+        self.skip_spaces();
+        let mut run = self.take_link_run().to_string();
+        if run.is_empty() {
+            return Ok(None);
+        }
+
+        let mut label = None;
+        // `-- text -->`, `-. text .->` and `== text ==>`
+        if matches!(run.as_str(), "--" | "-." | "==") {
+            let end = ["-->", "---", ".->", ".-", "==>", "==="]
+                .iter()
+                .filter_map(|close| self.s.find(close))
+                .min()
+                .with_context(|| format!("unterminated link `{}`", run))?;
+            label = Some(text(&self.s[..end]));
+            self.s = &self.s[end..];
+            run += self.take_link_run();
+        }
+
+        // `-->|text|`
+        self.skip_spaces();
+        if let Some(rest) = self.s.strip_prefix('|') {
+            let Some((l, rest)) = rest.split_once('|') else {
+                bail!("unterminated link label");
+            };
+            label = Some(text(l));
+            self.s = rest;
+        }
+
+        let style = if run.contains('.') {
+            Style::Dot
+        } else if run.contains('=') {
+            Style::SingleBold
+        } else {
+            Style::Single
+        };
+        Ok(Some(Link {
+            label,
+            has_arrow: run.ends_with('>'),
+            style,
+        }))
+    }
+
+    /// `group (link group)*`
+    fn parse_statement(&mut self) -> anyhow::Result<()> {
+        let mut group = self.parse_group()?;
+        while let Some(link) = self.parse_link()? {
+            let next = self.parse_group()?;
+            for &from in &group {
+                for &to in &next {
+                    self.graph.edges.push(Edge {
+                        from,
+                        to,
+                        label: link.label.clone(),
+                        has_arrow: link.has_arrow,
+                        style: link.style,
+                    });
+                }
+            }
+            group = next;
+        }
+
+        self.skip_spaces();
+        if !self.s.is_empty() {
+            bail!("unexpected `{}`", self.s);
+        }
+        Ok(())
+    }
+}
+
+/// Split the line into statements at `;` outside quotes, brackets and `|` of edge labels.
+fn statements(line: &str) -> Vec<&str> {
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    let (mut quoted, mut piped) = (false, false);
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => piped = !piped,
+            ';' if depth == 0 && !piped => {
+                statements.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&line[start..]);
+    statements
+}
+
+/// Make shapes from a Mermaid flowchart, laid out on the grid.
+/// Edges from a node to itself are not drawn, and are counted in the diagram.
+pub fn diagram(src: &str) -> anyhow::Result<Diagram> {
+    let mut graph = Graph::default();
+    let mut statements = src
+        .lines()
+        .enumerate()
+        .flat_map(|(i, l)| statements(l).into_iter().map(move |s| (i, s.trim())))
+        .filter(|(_, s)| !s.is_empty() && !s.starts_with("%%"));
+
+    let Some((_, header)) = statements.next() else {
+        bail!("empty flowchart");
+    };
+    let mut header = header.split_whitespace();
+    if !matches!(header.next(), Some("flowchart" | "graph")) {
+        bail!("not a flowchart");
+    }
+    graph.flow = match header.next() {
+        Some("TD" | "TB") | None => Flow::TopDown,
+        Some("BT") => Flow::BottomUp,
+        Some("LR") => Flow::LeftRight,
+        Some("RL") => Flow::RightLeft,
+        Some(d) => bail!("unknown direction `{}`", d),
+    };
+
+    for (i, statement) in statements {
+        let keyword = statement.split_whitespace().next().unwrap_or_default();
+        if IGNORED.contains(&keyword) {
+            continue;
+        }
+        let mut parser = Parser {
+            s: statement,
+            graph: &mut graph,
+        };
+        parser
+            .parse_statement()
+            .with_context(|| format!("line {}", i + 1))?;
+    }
+
+    Ok(super::graph::layout(&graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::canvas::Canvas;
+
+    #[test]
+    fn text_unquotes_and_breaks_lines() {
+        assert_eq!(text(" \"a b\" "), "a b");
+        assert_eq!(text("a<br>b<br/>c<br />d"), "a\nb\nc\nd");
+    }
+
+    #[test]
+    fn split_statements_outside_quotes_and_brackets() {
+        assert_eq!(
+            statements(r#"a["x; y"] --> b; b -->|p;q| c(z;w) ;"#),
+            [r#"a["x; y"] --> b"#, " b -->|p;q| c(z;w) ", ""]
+        );
+    }
+
+    #[test]
+    fn diagram_has_nodes_and_edges() {
+        let shapes = diagram("flowchart LR\n  A[start] -->|go| B(end)\n")
+            .unwrap()
+            .shapes;
+        let canvas: Canvas = shapes.into_iter().collect();
+        let text = canvas.to_plain_text();
+        for s in ["start", "end", "go", "▶"] {
+            assert!(text.contains(s), "{} is not in\n{}", s, text);
+        }
+    }
+
+    #[test]
+    fn semicolons_in_labels() {
+        let shapes = diagram("graph LR\n  a[\"x; y\"] -->|p;q| b; b --> c\n")
+            .unwrap()
+            .shapes;
+        let canvas: Canvas = shapes.into_iter().collect();
+        let text = canvas.to_plain_text();
+        for s in ["x; y", "p;q", " b ", " c "] {
+            assert!(text.contains(s), "{} is not in\n{}", s, text);
+        }
+    }
+}
@@ -10,13 +10,15 @@ use self::normal::NormalMode;
 use super::{
     canvas::{CanvasHandler, ShapeIdSet},
     cmd_line::CmdLine,
+    register,
     shape::Shape,
     AppOp,
 };
 use crate::util::Coord;
-use crossterm::event::Event;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use dummy::DummyMode;
 use ratatui::widgets::Paragraph;
+use std::collections::{HashMap, VecDeque};
 
 pub trait Mode {
     fn next(self: Box<Self>, e: Event, canvas_handler: &CanvasHandler) -> (Box<dyn Mode>, AppOp);
@@ -31,31 +33,380 @@ pub trait Mode {
         Default::default()
     }
 
+    /// Return true if the mode waits for a new command.
+    /// `.` repeats the last change only in this state.
+    fn is_idle(&self) -> bool {
+        false
+    }
+
+    /// Return true if digits are taken as the count of the next command.
+    fn takes_count(&self) -> bool {
+        false
+    }
+
+    /// Return true if the count repeats the event, i.e. the event is a motion or a change.
+    fn is_counted(&self, _e: &Event, _canvas_handler: &CanvasHandler) -> bool {
+        false
+    }
+
+    /// Return true if a prefix key, such as `"` and its register, waits for the rest of the
+    /// command. The count is kept for the rest.
+    fn is_pending(&self) -> bool {
+        false
+    }
+
     /// Message to show in command line.
-    fn status_msg(&self) -> Paragraph;
+    fn status_msg(&self) -> Paragraph<'_>;
 
-    fn cmd_line(&self) -> CmdLine {
+    fn cmd_line(&self) -> CmdLine<'_> {
         CmdLine::new(self.status_msg())
     }
 }
 
-pub struct ModeHandler(Box<dyn Mode>);
+pub struct ModeHandler {
+    mode: Box<dyn Mode>,
+    /// Events to process, including the replayed ones.
+    queue: VecDeque<Event>,
+    /// Count typed before the command.
+    count: Option<usize>,
+    /// Events of the prefix keys of the command in progress, repeated with the counted key.
+    prefix: Vec<Event>,
+    /// Events of the command repeated by the count, and the number of the remaining events.
+    repeat: Option<(Vec<Event>, usize)>,
+    /// Events of the command in progress, since the mode was idle.
+    change: Vec<Event>,
+    /// Events of the last command which changed the canvas, replayed by `.`.
+    last_change: Vec<Event>,
+    /// `q` or `@` waiting for the name of the register.
+    macro_prefix: Option<char>,
+    /// Register and events of the macro being recorded.
+    recording: Option<(char, Vec<Event>)>,
+    /// Recorded macros.
+    macros: HashMap<char, Vec<Event>>,
+    /// Register of the macro replayed last, replayed again by `@@`.
+    last_macro: Option<char>,
+    /// Number of macros replayed since the last event from the terminal.
+    num_replays: usize,
+}
+
+/// Max number of macros replayed for an event from the terminal,
+/// to stop recursive macros.
+const MAX_REPLAYS: usize = 1000;
+
+/// Max count of a command, not to block the screen for long with the repeats.
+const MAX_COUNT: usize = 1000;
 
 impl Default for ModeHandler {
     fn default() -> Self {
-        Self(Box::new(NormalMode::new()))
+        Self {
+            mode: Box::new(NormalMode::new()),
+            queue: Default::default(),
+            count: None,
+            prefix: vec![],
+            repeat: None,
+            change: vec![],
+            last_change: vec![],
+            macro_prefix: None,
+            recording: None,
+            macros: Default::default(),
+            last_macro: None,
+            num_replays: 0,
+        }
+    }
+}
+
+/// Char typed by the key event without modifiers other than shift.
+fn typed_char(e: &Event) -> Option<char> {
+    match e {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers,
+            ..
+        }) if (*modifiers - KeyModifiers::SHIFT).is_empty() => Some(*c),
+        _ => None,
     }
 }
 
 impl ModeHandler {
-    pub fn process_event(&mut self, event: Event, canvas_handler: &CanvasHandler) -> AppOp {
-        let current_mode = std::mem::replace(&mut self.0, Box::new(DummyMode::new()));
+    /// Queue the event to process by `next_op`, and record it if a macro is being recorded.
+    pub fn process_event(&mut self, event: Event) {
+        if let Some((_, events)) = &mut self.recording {
+            events.push(event.clone());
+        }
+        self.num_replays = 0;
+        self.queue.push_back(event);
+    }
+
+    /// Register of the macro being recorded.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(r, _)| *r)
+    }
+
+    /// Queue the events of the macro `count` times.
+    fn replay_macro(&mut self, register: char, count: usize) {
+        let Some(events) = self.macros.get(&register) else {
+            return;
+        };
+        self.last_macro = Some(register);
+        for _ in 0..count {
+            if self.num_replays >= MAX_REPLAYS {
+                self.queue.clear();
+                return;
+            }
+            self.num_replays += 1;
+            for e in events.iter().rev() {
+                self.queue.push_front(e.clone());
+            }
+        }
+    }
+
+    /// Process the queued events until the mode makes an operation, and return it.
+    /// Return `None` if all events are processed.
+    ///
+    /// The operation must be applied before the next call, since the mode may depend on
+    /// the canvas changed by it.
+    pub fn next_op(&mut self, canvas_handler: &CanvasHandler) -> Option<AppOp> {
+        let event = match self.repeat.take() {
+            Some((events, left)) => {
+                // The number of all the events is a multiple of the events of the command.
+                let event = events[(events.len() - left % events.len()) % events.len()].clone();
+                if left > 1 {
+                    self.repeat = Some((events, left - 1));
+                }
+                event
+            }
+            None => loop {
+                let event = self.queue.pop_front()?;
+                let is_idle = self.mode.is_idle();
+                let c = typed_char(&event);
+
+                // Record or replay a macro.
+                if let Some(prefix) = self.macro_prefix.take() {
+                    let count = self.count.take().unwrap_or(1);
+                    match (prefix, c) {
+                        ('q', Some(r)) if register::is_register(r) => {
+                            self.recording = Some((r, vec![]));
+                        }
+                        ('@', Some('@')) => {
+                            if let Some(r) = self.last_macro {
+                                self.replay_macro(r, count);
+                            }
+                        }
+                        ('@', Some(r)) if register::is_register(r) => self.replay_macro(r, count),
+                        _ => {}
+                    }
+                    continue;
+                }
+                if is_idle && c == Some('q') {
+                    match self.recording.take() {
+                        Some((r, mut events)) => {
+                            // Drop the `q` which stops recording.
+                            if events.last().and_then(typed_char) == Some('q') {
+                                events.pop();
+                            }
+                            self.macros.insert(r, events);
+                        }
+                        None => self.macro_prefix = Some('q'),
+                    }
+                    self.count = None;
+                    continue;
+                }
+                if is_idle && c == Some('@') {
+                    self.macro_prefix = Some('@');
+                    continue;
+                }
+
+                // Replay the last change.
+                if is_idle && c == Some('.') {
+                    for _ in 0..self.count.take().unwrap_or(1) {
+                        for e in self.last_change.iter().rev() {
+                            self.queue.push_front(e.clone());
+                        }
+                    }
+                    continue;
+                }
+
+                if is_idle && self.count.is_none() {
+                    self.change.clear();
+                }
+                self.change.push(event.clone());
+
+                // Count prefix.
+                if let Some(d) = c.and_then(|c| c.to_digit(10)) {
+                    if self.mode.takes_count() && (d > 0 || self.count.is_some()) {
+                        let count = self.count.unwrap_or(0).saturating_mul(10);
+                        self.count = Some(count.saturating_add(d as usize).min(MAX_COUNT));
+                        continue;
+                    }
+                }
+
+                // Repeat the command with its prefix keys by the count.
+                if self.mode.is_counted(&event, canvas_handler) {
+                    if let Some(n) = self.count.take().filter(|n| *n > 1) {
+                        let mut events = std::mem::take(&mut self.prefix);
+                        events.push(event.clone());
+                        let left = events.len().saturating_mul(n - 1);
+                        self.repeat = Some((events, left));
+                    }
+                } else {
+                    self.prefix.push(event.clone());
+                }
+                break event;
+            },
+        };
+
+        let current_mode = std::mem::replace(&mut self.mode, Box::new(DummyMode::new()));
         let (next_mode, app_op) = current_mode.next(event, canvas_handler);
-        self.0 = next_mode;
-        app_op
+        self.mode = next_mode;
+        if !self.mode.is_pending() {
+            self.count = None;
+            self.prefix.clear();
+        }
+        if app_op.is_change() {
+            self.last_change = self.change.clone();
+        }
+        Some(app_op)
     }
 
     pub fn get(&self) -> &dyn Mode {
-        self.0.as_ref()
+        self.mode.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Process the keys and return the names of the operations made.
+    /// Cursor moves and new shapes are applied to the canvas.
+    fn run(
+        mode_handler: &mut ModeHandler,
+        canvas_handler: &mut CanvasHandler,
+        keys: &str,
+    ) -> Vec<String> {
+        let mut ops = vec![];
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                c => KeyCode::Char(c),
+            };
+            mode_handler.process_event(Event::Key(KeyEvent::from(code)));
+            while let Some(op) = mode_handler.next_op(canvas_handler) {
+                ops.push(match op {
+                    AppOp::Nop => continue,
+                    AppOp::MoveCanvasCursor(d) => {
+                        canvas_handler.move_cursor(d);
+                        format!("{:?}", d)
+                    }
+                    AppOp::MakeShape(coord, shape) => {
+                        let record = shape.encode();
+                        canvas_handler.add_shape(coord, shape);
+                        record
+                    }
+                    AppOp::PutShapes(register, _) => {
+                        format!("put {}", register.unwrap_or(register::UNNAMED))
+                    }
+                    AppOp::SetMark(c) => format!("mark {}", c),
+                    _ => "other".to_string(),
+                });
+            }
+        }
+        ops
+    }
+
+    fn repeat(ops: &[&str], n: usize) -> Vec<String> {
+        ops.iter()
+            .cycle()
+            .take(ops.len() * n)
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn count_repeats_motion() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let ops = run(&mut mode_handler, &mut canvas_handler, "3l");
+        assert_eq!(ops, repeat(&["Right"], 3));
+        let ops = run(&mut mode_handler, &mut canvas_handler, "10j");
+        assert_eq!(ops, repeat(&["Down"], 10));
+        assert_eq!(canvas_handler.cursor_coord(), Coord::new(3, 10));
+    }
+
+    #[test]
+    fn dot_repeats_last_change() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let rect = ["Right", "Right", "rect 3 1 single"];
+        let ops = run(&mut mode_handler, &mut canvas_handler, "rll\n");
+        assert_eq!(ops, repeat(&rect, 1));
+        let ops = run(&mut mode_handler, &mut canvas_handler, "l.");
+        assert_eq!(ops, ["Right"].into_iter().chain(rect).collect::<Vec<_>>());
+        let ops = run(&mut mode_handler, &mut canvas_handler, "2.");
+        assert_eq!(ops, repeat(&rect, 2));
+    }
+
+    #[test]
+    fn count_applies_to_motions_and_changes() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let ops = run(&mut mode_handler, &mut canvas_handler, "3xj");
+        assert_eq!(ops, ["Down"]);
+        let ops = run(&mut mode_handler, &mut canvas_handler, "3ma");
+        assert_eq!(ops, ["mark a"]);
+        let ops = run(&mut mode_handler, &mut canvas_handler, "r3l\n");
+        assert_eq!(ops, ["Right", "Right", "Right", "rect 4 1 single"]);
+    }
+
+    #[test]
+    fn put_once_with_count() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let ops = run(&mut mode_handler, &mut canvas_handler, "3\"ap");
+        assert_eq!(ops, ["put a"]);
+        let ops = run(&mut mode_handler, &mut canvas_handler, "\"a3pl");
+        assert_eq!(ops, ["put a", "Right"]);
+    }
+
+    #[test]
+    fn cap_count() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let huge = "99999999999999999999";
+        let ops = run(
+            &mut mode_handler,
+            &mut canvas_handler,
+            &format!("{}l", huge),
+        );
+        assert_eq!(ops.len(), MAX_COUNT);
+        let ops = run(
+            &mut mode_handler,
+            &mut canvas_handler,
+            &format!("{}\"ap", huge),
+        );
+        assert_eq!(ops, ["put a"]);
+        run(&mut mode_handler, &mut canvas_handler, "rl\n");
+        let ops = run(
+            &mut mode_handler,
+            &mut canvas_handler,
+            &format!("{}.", huge),
+        );
+        assert_eq!(ops, repeat(&["Right", "rect 2 1 single"], MAX_COUNT));
+    }
+
+    #[test]
+    fn replay_macro() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let ops = run(&mut mode_handler, &mut canvas_handler, "qaljq");
+        assert_eq!(ops, ["Right", "Down"]);
+        assert_eq!(mode_handler.recording(), None);
+        let ops = run(&mut mode_handler, &mut canvas_handler, "@a");
+        assert_eq!(ops, ["Right", "Down"]);
+        let ops = run(&mut mode_handler, &mut canvas_handler, "2@a");
+        assert_eq!(ops, repeat(&["Right", "Down"], 2));
+        let ops = run(&mut mode_handler, &mut canvas_handler, "@@");
+        assert_eq!(ops, ["Right", "Down"]);
+    }
+
+    #[test]
+    fn stop_recursive_macro() {
+        let (mut mode_handler, mut canvas_handler) = Default::default();
+        let ops = run(&mut mode_handler, &mut canvas_handler, "qal@aq@a");
+        assert_eq!(ops.len(), MAX_REPLAYS + 1);
     }
 }
@@ -1,11 +1,12 @@
 use super::{normal::NormalMode, Mode};
-use crate::app::{canvas::CanvasHandler, AppOp};
+use crate::app::{canvas::CanvasHandler, export::Format, AppOp};
 use crossterm::event::{Event, KeyCode};
 use ratatui::{
     layout::Alignment,
     style::{Color, Style},
     widgets::{Paragraph, Wrap},
 };
+use std::path::PathBuf;
 
 /// Operations for command mode.
 enum Op {
@@ -45,6 +46,96 @@ impl CmdMode {
     }
 }
 
+impl CmdMode {
+    /// Operation for the entered command.
+    fn app_op(&self) -> AppOp {
+        let cmd = self.cmd.trim_start_matches(':').trim();
+        let (name, arg) = match cmd.split_once(' ') {
+            Some((name, arg)) => (name, Some(arg.trim())),
+            None => (cmd, None),
+        };
+
+        match (name, arg) {
+            ("", None) => AppOp::Nop,
+            ("q", None) => AppOp::QuitApp(false),
+            ("q!", None) => AppOp::QuitApp(true),
+            ("w", None) => AppOp::WriteDocument(None),
+            ("w", Some(arg)) => match arg.parse() {
+                Ok(storage) => AppOp::WriteDocument(Some(storage)),
+                Err(e) => AppOp::ShowNotice(format!("{:#}", e)),
+            },
+            ("wq", None) => AppOp::WriteDocumentAndQuit(None),
+            ("wq", Some(arg)) => match arg.parse() {
+                Ok(storage) => AppOp::WriteDocumentAndQuit(Some(storage)),
+                Err(e) => AppOp::ShowNotice(format!("{:#}", e)),
+            },
+            ("e!", None) => AppOp::ReloadDocument,
+            ("merge", None) => AppOp::MergeDocument,
+            ("e" | "e!", Some(arg)) => match arg.parse() {
+                Ok(storage) => AppOp::EditDocument(storage, name == "e!"),
+                Err(e) => AppOp::ShowNotice(format!("{:#}", e)),
+            },
+            ("recover", None) => AppOp::RecoverSwap,
+            ("discard", None) => AppOp::DiscardSwap,
+            ("e" | "export" | "r" | "import", None) => {
+                AppOp::ShowNotice("No file name".to_string())
+            }
+            ("export", Some(arg)) => Self::export_op(arg),
+            ("r" | "import", Some(arg)) => Self::import_op(arg),
+            _ => AppOp::ShowNotice(format!("Not an editor command: {}", cmd)),
+        }
+    }
+
+    /// Split leading `--<option>`s from the argument.
+    fn split_options(arg: &str) -> (Vec<&str>, &str) {
+        let mut options = vec![];
+        let mut rest = arg.trim_start();
+        while rest.starts_with("--") {
+            let (option, r) = rest.split_once(' ').unwrap_or((rest, ""));
+            options.push(option);
+            rest = r.trim_start();
+        }
+        (options, rest)
+    }
+
+    /// Operation for `:export [--css|--ansi] <file>`.
+    fn export_op(arg: &str) -> AppOp {
+        let (options, path) = Self::split_options(arg);
+        if path.is_empty() {
+            return AppOp::ShowNotice("No file name".to_string());
+        }
+
+        let path = PathBuf::from(path);
+        let mut format = Format::from_path(&path);
+        for option in options {
+            match option {
+                "--css" => format = Format::Html { css: true },
+                "--ansi" => format = Format::Ansi,
+                _ => return AppOp::ShowNotice(format!("Unknown option: {}", option)),
+            }
+        }
+        AppOp::ExportCanvas(path, format)
+    }
+
+    /// Operation for `:import [--style=<style>] <file>`.
+    fn import_op(arg: &str) -> AppOp {
+        let (options, path) = Self::split_options(arg);
+        if path.is_empty() {
+            return AppOp::ShowNotice("No file name".to_string());
+        }
+
+        let mut style = None;
+        for option in options {
+            match option.strip_prefix("--style=").map(str::parse) {
+                Some(Ok(s)) => style = Some(s),
+                Some(Err(e)) => return AppOp::ShowNotice(format!("{:#}", e)),
+                None => return AppOp::ShowNotice(format!("Unknown option: {}", option)),
+            }
+        }
+        AppOp::ImportFile(PathBuf::from(path), style)
+    }
+}
+
 impl Default for CmdMode {
     fn default() -> Self {
         Self::new()
@@ -55,12 +146,7 @@ impl Mode for CmdMode {
     fn next(mut self: Box<Self>, e: Event, _: &CanvasHandler) -> (Box<dyn Mode>, AppOp) {
         match e.into() {
             Op::Enter => {
-                let app_op = if self.cmd == ":q" {
-                    AppOp::QuitApp
-                } else {
-                    AppOp::Nop
-                };
-
+                let app_op = self.app_op();
                 let next_mode = Box::new(NormalMode::new());
                 (next_mode, app_op)
             }
@@ -80,7 +166,7 @@ impl Mode for CmdMode {
         }
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
         let t = ratatui::text::Text::raw(self.cmd.clone());
         Paragraph::new(t)
             .style(
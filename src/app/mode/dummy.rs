@@ -15,7 +15,7 @@ impl Mode for DummyMode {
         unreachable!();
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
         unreachable!();
     }
 }
@@ -82,7 +82,7 @@ impl Mode for MakePathMode {
                 (self, AppOp::MoveCanvasCursor(dir.opposite()))
             }
             Op::MakePath => {
-                let mode = Box::new(NormalMode);
+                let mode = Box::new(NormalMode::new());
                 if self.path.is_empty() {
                     return (mode, AppOp::Nop);
                 }
@@ -105,7 +105,15 @@ impl Mode for MakePathMode {
         vec![(start, Box::new(line))]
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
+    fn takes_count(&self) -> bool {
+        true
+    }
+
+    fn is_counted(&self, e: &Event, _: &crate::app::canvas::CanvasHandler) -> bool {
+        matches!(e.clone().into(), Op::MoveCursor(_))
+    }
+
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
         let t = ratatui::text::Text::raw("LINE [Enter]Complete, [s]Change Line Style");
         Paragraph::new(t)
             .style(
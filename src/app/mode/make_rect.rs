@@ -86,7 +86,7 @@ impl Mode for MakeRectMode {
                 let (start, rect) =
                     Self::make_rect(self.start_coord, canvas_handler.cursor_coord(), self.style);
                 let op = AppOp::MakeShape(start, Box::new(rect));
-                let mode = Box::new(NormalMode);
+                let mode = Box::new(NormalMode::new());
                 (mode, op)
             }
         }
@@ -97,7 +97,15 @@ impl Mode for MakeRectMode {
         vec![(start, Box::new(rect))]
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
+    fn takes_count(&self) -> bool {
+        true
+    }
+
+    fn is_counted(&self, e: &Event, _: &CanvasHandler) -> bool {
+        matches!(e.clone().into(), Op::MoveCursor(_))
+    }
+
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
         let t = ratatui::text::Text::raw("RECT [Enter]Complete, [s]Change Line Style");
         Paragraph::new(t)
             .style(
@@ -2,7 +2,10 @@ use super::{normal::NormalMode, Mode};
 use crate::{
     app::{
         canvas::CanvasHandler,
-        shape::{text::Text, Shape},
+        shape::{
+            text::{self, Text},
+            Shape,
+        },
         AppOp,
     },
     util::Coord,
@@ -20,6 +23,7 @@ enum Op {
     AddChar(char),
     Enter,
     Backspace,
+    Paste(String),
     Nop,
 }
 
@@ -33,6 +37,7 @@ impl From<Event> for Op {
                 KeyCode::Esc => Op::MakeText,
                 _ => Op::Nop,
             },
+            Event::Paste(s) => Op::Paste(s),
             _ => Op::Nop,
         }
     }
@@ -62,14 +67,14 @@ impl Mode for MakeTextMode {
         match e.into() {
             Op::Nop => (self, AppOp::Nop),
             Op::MakeText => {
-                let mode = Box::new(NormalMode);
+                let mode = Box::new(NormalMode::new());
                 let text = Box::new(Text::new(self.text.clone()));
                 let op = AppOp::MakeShape(self.start_coord, text);
                 (mode, op)
             }
             Op::AddChar(c) => {
                 self.text.push(c);
-                cursor_coord.x += UnicodeWidthChar::width(c).unwrap() as i16;
+                cursor_coord.x += UnicodeWidthChar::width(c).unwrap_or(0) as i16;
                 (self, AppOp::SetCanvasCursor(cursor_coord))
             }
             Op::Enter => {
@@ -78,6 +83,17 @@ impl Mode for MakeTextMode {
                 cursor_coord.x = self.start_coord.x;
                 (self, AppOp::SetCanvasCursor(cursor_coord))
             }
+            Op::Paste(s) => {
+                let last_line = self.text.rsplit('\n').next().unwrap_or("");
+                let column = UnicodeWidthStr::width(last_line);
+                self.text += &text::sanitize(&s, column);
+
+                // Move the cursor to the end of the text.
+                let last_line = self.text.rsplit('\n').next().unwrap_or("");
+                cursor_coord.y = self.start_coord.y + self.text.matches('\n').count() as i16;
+                cursor_coord.x = self.start_coord.x + UnicodeWidthStr::width(last_line) as i16;
+                (self, AppOp::SetCanvasCursor(cursor_coord))
+            }
             Op::Backspace => {
                 let c = self.text.pop();
                 match c {
@@ -88,7 +104,7 @@ impl Mode for MakeTextMode {
                         cursor_coord.x += last_line_width;
                     }
                     Some(c) => {
-                        cursor_coord.x -= UnicodeWidthChar::width(c).unwrap() as i16;
+                        cursor_coord.x -= UnicodeWidthChar::width(c).unwrap_or(0) as i16;
                     }
                     _ => {}
                 }
@@ -102,7 +118,7 @@ impl Mode for MakeTextMode {
         vec![(self.start_coord, Box::new(text))]
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
         let t = ratatui::text::Text::raw("TEXT [Esc]Complete");
         Paragraph::new(t)
             .style(
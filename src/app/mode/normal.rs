@@ -5,11 +5,13 @@ use super::{
 use crate::{
     app::{
         canvas::{CanvasHandler, ShapeId},
+        document, register,
+        shape::text::{self, Text},
         AppOp,
     },
-    util::Direction,
+    util::{Coord, Direction},
 };
-use crossterm::event::{Event, KeyCode};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::Alignment,
     style::{Color, Style},
@@ -28,6 +30,23 @@ enum Op {
     EnterMakeText,
     /// Move Cursor
     MoveCursor(Direction),
+    /// Paste the string at the cursor.
+    Paste(String),
+    /// Wait for the argument of the prefix key: `"` (register), `m` (set mark) or `'` (jump to
+    /// mark).
+    Prefix(char),
+    /// Go back to the position before the last jump.
+    JumpOlder,
+    /// Go forward in the jump list.
+    JumpNewer,
+    /// Put the shapes in the register, ending at the cursor if true.
+    Put(bool),
+    /// Copy the rendered canvas to the system clipboard.
+    YankText,
+    /// Undo the last change.
+    Undo,
+    /// Redo the last undone change.
+    Redo,
     /// Toggle the selection state of the shape directly under the cursor.
     EnterSelectShape(ShapeId),
     /// Do nothing.
@@ -37,6 +56,12 @@ enum Op {
 impl From<(Event, &CanvasHandler)> for Op {
     fn from((e, ch): (Event, &CanvasHandler)) -> Self {
         match e {
+            Event::Key(k) if k.modifiers.contains(KeyModifiers::CONTROL) => match k.code {
+                KeyCode::Char('r') => Op::Redo,
+                KeyCode::Char('o') => Op::JumpOlder,
+                KeyCode::Char('i') => Op::JumpNewer,
+                _ => Op::Nop,
+            },
             Event::Key(k) => match k.code {
                 KeyCode::Char(c) => match c {
                     ':' => Op::EnterCmd,
@@ -45,26 +70,66 @@ impl From<(Event, &CanvasHandler)> for Op {
                     'k' => Op::MoveCursor(Direction::Up),
                     'l' => Op::MoveCursor(Direction::Right),
                     'r' => Op::EnterMakeRect,
-                    'p' => Op::EnterMakePath,
+                    'a' => Op::EnterMakePath,
+                    '"' | 'm' | '\'' => Op::Prefix(c),
+                    'p' => Op::Put(false),
+                    'P' => Op::Put(true),
                     't' => Op::EnterMakeText,
+                    'Y' => Op::YankText,
+                    'u' => Op::Undo,
                     ' ' => match ch.shape_id_under_the_cursor() {
                         Some(id) => Op::EnterSelectShape(id),
                         None => Op::Nop,
                     },
                     _ => Op::Nop,
                 },
+                // Ctrl-i is sent as Tab by most terminals.
+                KeyCode::Tab => Op::JumpNewer,
                 _ => Op::Nop,
             },
+            Event::Paste(s) => Op::Paste(s),
             _ => Op::Nop,
         }
     }
 }
 
-pub struct NormalMode;
+/// Operation to paste the string at the cursor.
+/// A native document is pasted as its shapes, and other strings as a text.
+fn paste_op(s: &str, cursor: Coord) -> AppOp {
+    if s.is_empty() {
+        return AppOp::Nop;
+    }
+    let Ok(shapes) = document::decode(s) else {
+        let text = Text::new(text::sanitize(s, 0));
+        return AppOp::MakeShape(cursor, Box::new(text));
+    };
+
+    // Keep the relative coords of the shapes, putting their upper-left at the cursor.
+    let origin = shapes
+        .iter()
+        .map(|(c, _)| *c)
+        .reduce(|a, b| Coord::new(a.x.min(b.x), a.y.min(b.y)))
+        .unwrap_or_default();
+    let shapes = shapes
+        .into_iter()
+        .map(|(c, s)| (cursor + c.offset(origin), s))
+        .collect();
+    AppOp::MakeShapes(shapes)
+}
+
+pub struct NormalMode {
+    /// Register for the next operation.
+    register: Option<char>,
+    /// Prefix key waiting for its argument.
+    prefix: Option<char>,
+}
 
 impl NormalMode {
     pub fn new() -> Self {
-        Self
+        Self {
+            register: None,
+            prefix: None,
+        }
     }
 }
 
@@ -75,8 +140,36 @@ impl Default for NormalMode {
 }
 
 impl Mode for NormalMode {
-    fn next(self: Box<Self>, e: Event, canvas_handler: &CanvasHandler) -> (Box<dyn Mode>, AppOp) {
+    fn next(
+        mut self: Box<Self>,
+        e: Event,
+        canvas_handler: &CanvasHandler,
+    ) -> (Box<dyn Mode>, AppOp) {
+        if let Some(prefix) = self.prefix.take() {
+            let Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) = e
+            else {
+                return (self, AppOp::Nop);
+            };
+            let op = match prefix {
+                '"' => {
+                    self.register = Some(c).filter(|c| register::is_register(*c));
+                    AppOp::Nop
+                }
+                'm' if c.is_ascii_lowercase() => AppOp::SetMark(c),
+                '\'' if c.is_ascii_lowercase() => match canvas_handler.mark(c) {
+                    Some(coord) => AppOp::SetCanvasCursor(coord),
+                    None => AppOp::ShowNotice(format!("Mark {} not set", c)),
+                },
+                _ => AppOp::Nop,
+            };
+            return (self, op);
+        }
+
         let cursor = canvas_handler.cursor();
+        let register = self.register.take();
         match (e, canvas_handler).into() {
             Op::EnterCmd => {
                 let cmd = Box::new(CmdMode::new());
@@ -87,12 +180,50 @@ impl Mode for NormalMode {
             Op::EnterMakeRect => (Box::new(MakeRectMode::new(cursor.coord())), AppOp::Nop),
             Op::EnterMakePath => (Box::new(MakePathMode::new(cursor.coord())), AppOp::Nop),
             Op::EnterMakeText => (Box::new(MakeTextMode::new(cursor.coord())), AppOp::Nop),
+            Op::Paste(s) => (self, paste_op(&s, cursor.coord())),
+            Op::Prefix(c) => {
+                self.prefix = Some(c);
+                (self, AppOp::Nop)
+            }
+            Op::JumpOlder => (self, AppOp::JumpOlder),
+            Op::JumpNewer => (self, AppOp::JumpNewer),
+            Op::Put(before) => (self, AppOp::PutShapes(register, before)),
+            Op::YankText => (self, AppOp::YankText(None)),
+            Op::Undo => (self, AppOp::Undo),
+            Op::Redo => (self, AppOp::Redo),
             Op::EnterSelectShape(id) => (Box::new(SelectMode::new(id)), AppOp::Nop),
         }
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
-        let t = ratatui::text::Text::raw("NORM [:]cmd [r]rect [t]text [p]path [SP]select");
+    fn is_idle(&self) -> bool {
+        self.register.is_none() && self.prefix.is_none()
+    }
+
+    fn takes_count(&self) -> bool {
+        self.prefix.is_none()
+    }
+
+    fn is_counted(&self, e: &Event, canvas_handler: &CanvasHandler) -> bool {
+        self.prefix.is_none()
+            && matches!(
+                (e.clone(), canvas_handler).into(),
+                Op::MoveCursor(_) | Op::Undo | Op::Redo | Op::JumpOlder | Op::JumpNewer
+            )
+    }
+
+    fn is_pending(&self) -> bool {
+        !self.is_idle()
+    }
+
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
+        let register = match self.register {
+            Some(r) => format!("\"{} ", r),
+            None => String::new(),
+        };
+        let t = ratatui::text::Text::raw(format!(
+            "{}NORM [:]cmd [r]rect [t]text [a]path [SP]select [p/P]put [Y]yank text [u]undo [^r]redo [m]mark [']jump",
+            register
+        ));
         Paragraph::new(t)
             .style(
                 Style::default()
@@ -2,11 +2,11 @@ use super::{normal::NormalMode, Mode};
 use crate::{
     app::{
         canvas::{CanvasHandler, ShapeId, ShapeIdSet},
-        AppOp,
+        register, AppOp,
     },
     util::Direction,
 };
-use crossterm::event::{Event, KeyCode};
+use crossterm::event::{Event, KeyCode, KeyEvent};
 use ratatui::{
     layout::Alignment,
     style::{Color, Style},
@@ -18,6 +18,11 @@ enum Op {
     MoveCursor(Direction),
     MoveShapes(Direction),
     DeleteShapes,
+    YankText,
+    /// Choose the register for the next operation.
+    SelectRegister,
+    /// Copy the selected shapes into the register.
+    YankShapes,
     EnterNormalMode,
     Nop,
 }
@@ -33,6 +38,9 @@ impl From<(Event, &CanvasHandler)> for Op {
                         None => Op::Nop,
                     },
                     'd' => Op::DeleteShapes,
+                    'Y' => Op::YankText,
+                    '"' => Op::SelectRegister,
+                    'y' => Op::YankShapes,
                     'h' => Op::MoveCursor(Direction::Left),
                     'j' => Op::MoveCursor(Direction::Down),
                     'k' => Op::MoveCursor(Direction::Up),
@@ -52,6 +60,10 @@ impl From<(Event, &CanvasHandler)> for Op {
 
 pub struct SelectMode {
     selected_shapes: ShapeIdSet,
+    /// Register for the next operation.
+    register: Option<char>,
+    /// True if the next key is the name of the register.
+    awaiting_register: bool,
 }
 
 impl SelectMode {
@@ -59,7 +71,11 @@ impl SelectMode {
     pub fn new(id: ShapeId) -> Self {
         let mut selected_shapes = ShapeIdSet::default();
         selected_shapes.insert(&id);
-        Self { selected_shapes }
+        Self {
+            selected_shapes,
+            register: None,
+            awaiting_register: false,
+        }
     }
 }
 
@@ -69,6 +85,19 @@ impl Mode for SelectMode {
         e: Event,
         canvas_hanler: &CanvasHandler,
     ) -> (Box<dyn Mode>, crate::app::AppOp) {
+        if self.awaiting_register {
+            self.awaiting_register = false;
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) = e
+            {
+                self.register = Some(c).filter(|c| register::is_register(*c));
+            }
+            return (self, AppOp::Nop);
+        }
+
+        let register = self.register.take();
         match (e, canvas_hanler).into() {
             Op::ToggleSelect(id) => {
                 self.selected_shapes.toggle(&id);
@@ -87,13 +116,52 @@ impl Mode for SelectMode {
                 Box::new(NormalMode::new()),
                 AppOp::DeleteShapes(self.selected_shapes),
             ),
+            Op::YankText => (
+                Box::new(NormalMode::new()),
+                AppOp::YankText(Some(self.selected_shapes)),
+            ),
+            Op::SelectRegister => {
+                self.awaiting_register = true;
+                (self, AppOp::Nop)
+            }
+            Op::YankShapes => (
+                Box::new(NormalMode::new()),
+                AppOp::YankShapes(register, self.selected_shapes),
+            ),
             Op::EnterNormalMode => (Box::new(NormalMode::new()), AppOp::Nop),
             Op::Nop => (self, AppOp::Nop),
         }
     }
 
-    fn status_msg(&self) -> ratatui::widgets::Paragraph {
-        let t = ratatui::text::Text::raw("SELECT [sp]toggle select [d]delete [S-h/j/k/l]move");
+    fn is_idle(&self) -> bool {
+        self.register.is_none() && !self.awaiting_register
+    }
+
+    fn takes_count(&self) -> bool {
+        !self.awaiting_register
+    }
+
+    fn is_counted(&self, e: &Event, canvas_handler: &CanvasHandler) -> bool {
+        !self.awaiting_register
+            && matches!(
+                (e.clone(), canvas_handler).into(),
+                Op::MoveCursor(_) | Op::MoveShapes(_)
+            )
+    }
+
+    fn is_pending(&self) -> bool {
+        !self.is_idle()
+    }
+
+    fn status_msg(&self) -> ratatui::widgets::Paragraph<'_> {
+        let register = match self.register {
+            Some(r) => format!("\"{} ", r),
+            None => String::new(),
+        };
+        let t = ratatui::text::Text::raw(format!(
+            "{}SELECT [sp]toggle select [d]delete [y]yank [Y]yank text [S-h/j/k/l]move",
+            register
+        ));
         Paragraph::new(t)
             .style(
                 Style::default()
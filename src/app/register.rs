@@ -0,0 +1,121 @@
+//! Registers to yank and put shapes, like the registers of vim.
+//!
+//! The unnamed register `"` is used if no register is given, and named registers `a` to `z`
+//! keep their shapes until they are yanked again. Yanking into a named register also sets the
+//! unnamed register.
+
+use super::{
+    canvas::{Canvas, ShapeIdSet},
+    shape::Shape,
+};
+use crate::util::Coord;
+use std::collections::HashMap;
+
+/// Register used if no register is given.
+pub const UNNAMED: char = '"';
+
+/// Return true if `c` names a register.
+pub fn is_register(c: char) -> bool {
+    c == UNNAMED || c.is_ascii_lowercase()
+}
+
+#[derive(Default)]
+pub struct Registers(HashMap<char, Canvas>);
+
+impl Registers {
+    /// Copy the shapes into the register, and return the number of them.
+    /// The coords of the shapes are kept relative to their upper-left corner.
+    pub fn yank(&mut self, register: Option<char>, canvas: &Canvas, ids: &ShapeIdSet) -> usize {
+        let subset = canvas.subset(ids);
+        let origin = subset
+            .shapes()
+            .map(|(c, _)| *c)
+            .reduce(|a, b| Coord::new(a.x.min(b.x), a.y.min(b.y)))
+            .unwrap_or_default();
+        let copy = || -> Canvas {
+            subset
+                .shapes()
+                .map(|(c, s)| (c.offset(origin), s.clone_box()))
+                .collect()
+        };
+
+        let register = register.unwrap_or(UNNAMED);
+        if register != UNNAMED {
+            self.0.insert(UNNAMED, copy());
+        }
+        self.0.insert(register, copy());
+        subset.shapes().count()
+    }
+
+    /// Copy of the shapes in the register, placed at `coord`.
+    ///
+    /// If `before` is false, the upper-left corner of the shapes is put at `coord`.
+    /// Otherwise, the lower-right corner is.
+    pub fn put(
+        &self,
+        register: Option<char>,
+        coord: Coord,
+        before: bool,
+    ) -> Option<Vec<(Coord, Box<dyn Shape>)>> {
+        let yanked = self.0.get(&register.unwrap_or(UNNAMED))?;
+        let origin = match (before, yanked.bounding_box()) {
+            (true, Some((min, size))) => {
+                coord - min - Coord::new(size.width as i16 - 1, size.height as i16 - 1)
+            }
+            _ => coord,
+        };
+        Some(
+            yanked
+                .shapes()
+                .map(|(c, s)| (origin + *c, s.clone_box()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::shape::text::Text;
+
+    fn yanked() -> Registers {
+        let mut canvas = Canvas::default();
+        let mut ids = ShapeIdSet::default();
+        ids.insert(&canvas.add_shape(Coord::new(2, 3), Box::new(Text::new("ab".into()))));
+        ids.insert(&canvas.add_shape(Coord::new(5, 4), Box::new(Text::new("c".into()))));
+        canvas.add_shape(Coord::new(0, 0), Box::new(Text::new("d".into())));
+
+        let mut registers = Registers::default();
+        assert_eq!(registers.yank(Some('a'), &canvas, &ids), 2);
+        registers
+    }
+
+    fn coords(shapes: Option<Vec<(Coord, Box<dyn Shape>)>>) -> Vec<Coord> {
+        shapes.unwrap().into_iter().map(|(c, _)| c).collect()
+    }
+
+    #[test]
+    fn put_at_cursor() {
+        let registers = yanked();
+        let cursor = Coord::new(10, 10);
+        assert_eq!(
+            coords(registers.put(Some('a'), cursor, false)),
+            [Coord::new(10, 10), Coord::new(13, 11)]
+        );
+        assert_eq!(
+            coords(registers.put(Some('a'), cursor, true)),
+            [Coord::new(7, 9), Coord::new(10, 10)]
+        );
+    }
+
+    #[test]
+    fn yank_into_named_sets_unnamed() {
+        let registers = yanked();
+        let cursor = Coord::new(0, 0);
+        assert_eq!(
+            coords(registers.put(None, cursor, false)),
+            coords(registers.put(Some('a'), cursor, false))
+        );
+        assert!(registers.put(Some('b'), cursor, false).is_none());
+    }
+}
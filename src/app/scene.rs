@@ -0,0 +1,162 @@
+//! JSON scene format, to make and process documents from other programs.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "shapes": [
+//!     { "kind": "rect", "coord": { "x": 0, "y": 0 },
+//!       "size": { "width": 10, "height": 5 }, "style": "single" },
+//!     { "kind": "path", "coord": { "x": 9, "y": 2 }, "style": "dash",
+//!       "directions": ["right", "right", "down"], "start_arrow": false, "end_arrow": true },
+//!     { "kind": "text", "coord": { "x": 2, "y": 2 }, "text": "hello\nworld" },
+//!     { "kind": "table", "coord": { "x": 0, "y": 6 }, "style": "single",
+//!       "cells": [["name", "age"], ["bob", "42"]] }
+//!   ]
+//! }
+//! ```
+//!
+//! - `version` : version of the format, which is 1.
+//! - `shapes` : shapes in z-order (from back to front).
+//! - `kind` : `rect`, `path`, `text` or `table`.
+//! - `coord` : coord of the upper-left corner of the shape. `x` grows rightward, `y` downward.
+//! - `size` : size of the rect in cells, including the border.
+//! - `style` : `single`, `single-bold`, `double`, `dash`, `dot` or `ascii`.
+//! - `directions` : moves from the start cell of the path, each of which is `up`, `down`,
+//!   `left` or `right`.
+//! - `start_arrow`, `end_arrow` : arrowheads at the ends of the path, `false` if omitted.
+//! - `text` : text with `\n` as line breaks.
+//! - `cells` : rows of the cells of the table. Short rows are padded with empty cells.
+//!
+//! Unknown fields are ignored.
+
+use super::shape::{path::Path, rect::Rect, style::Style, table::Table, text::Text, Shape};
+use crate::util::{Coord, Direction, Size};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Scene {
+    version: u32,
+    shapes: Vec<Node>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Node {
+    coord: Coord,
+    #[serde(flatten)]
+    shape: SceneShape,
+}
+
+/// Shape in the scene, tagged by `kind`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SceneShape {
+    Rect {
+        size: Size,
+        style: Style,
+    },
+    Path {
+        style: Style,
+        directions: Vec<Direction>,
+        #[serde(default)]
+        start_arrow: bool,
+        #[serde(default)]
+        end_arrow: bool,
+    },
+    Text {
+        text: String,
+    },
+    Table {
+        style: Style,
+        cells: Vec<Vec<String>>,
+    },
+}
+
+impl SceneShape {
+    fn into_shape(self) -> anyhow::Result<Box<dyn Shape>> {
+        let shape: Box<dyn Shape> = match self {
+            SceneShape::Rect { size, style } => {
+                anyhow::ensure!(size.width > 0 && size.height > 0, "rect must not be empty");
+                Box::new(Rect::new(size, style))
+            }
+            SceneShape::Path {
+                style,
+                directions,
+                start_arrow,
+                end_arrow,
+            } => Box::new(Path::new(directions, start_arrow, end_arrow, style)),
+            SceneShape::Text { text } => Box::new(Text::new(text)),
+            SceneShape::Table { style, cells } => {
+                anyhow::ensure!(!cells.is_empty(), "table must not be empty");
+                Box::new(Table::new(cells, style))
+            }
+        };
+        Ok(shape)
+    }
+}
+
+/// Encode shapes (ordered from back to front) into a scene.
+pub fn encode<'a>(shapes: impl Iterator<Item = &'a (Coord, Box<dyn Shape>)>) -> String {
+    let scene = Scene {
+        version: VERSION,
+        shapes: shapes
+            .map(|(coord, shape)| Node {
+                coord: *coord,
+                shape: shape.to_scene(),
+            })
+            .collect(),
+    };
+    let mut json = serde_json::to_string_pretty(&scene).expect("scene is always serializable");
+    json.push('\n');
+    json
+}
+
+/// Decode a scene into shapes ordered from back to front.
+pub fn decode(json: &str) -> anyhow::Result<Vec<(Coord, Box<dyn Shape>)>> {
+    let scene: Scene = serde_json::from_str(json).context("malformed scene")?;
+    anyhow::ensure!(
+        scene.version == VERSION,
+        "unsupported scene version {}",
+        scene.version
+    );
+
+    scene
+        .shapes
+        .into_iter()
+        .enumerate()
+        .map(|(i, node)| {
+            let shape = node
+                .shape
+                .into_shape()
+                .with_context(|| format!("shape {}", i))?;
+            Ok((node.coord, shape))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::document;
+
+    #[test]
+    fn round_trip() {
+        let doc = "uart 1
+0 0 rect 10 5 single
+9 2 path dash > llllljj
+2 2 text hello\\nworld
+0 6 table single 2 name|age|bob|42
+";
+        let shapes = document::decode(doc).unwrap();
+        let json = encode(shapes.iter());
+        assert_eq!(document::encode(decode(&json).unwrap().iter()), doc);
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        assert!(decode(r#"{"version": 2, "shapes": []}"#).is_err());
+        assert!(decode("[]").is_err());
+    }
+}
@@ -1,10 +1,12 @@
 pub mod path;
 pub mod rect;
 pub mod style;
+pub mod table;
 pub mod text;
 
+use super::{export::svg, scene::SceneShape};
 use crate::util::{Coord, IterExt, Size, StrExt};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use std::ops::Range;
 use unicode_width::UnicodeWidthChar;
 
@@ -14,6 +16,11 @@ pub trait Shape: ToString {
     /// Fill the shape.
     fn fill(&self) -> bool;
 
+    /// Text attributes of the shape, such as bold.
+    fn modifier(&self) -> Modifier {
+        Modifier::empty()
+    }
+
     fn render(
         &self,
         offset: Coord,
@@ -69,7 +76,11 @@ pub trait Shape: ToString {
             .collect();
 
         // Render
-        let style = Style::default().fg(color);
+        // Replace the attributes of the shapes behind.
+        let style = Style::default()
+            .fg(color)
+            .add_modifier(self.modifier())
+            .remove_modifier(Modifier::all() - self.modifier());
         let shape_area = ratatui::layout::Rect::new(
             area.x + 0.max(offset.x) as u16,
             area.y + 0.max(offset.y) as u16,
@@ -88,10 +99,29 @@ pub trait Shape: ToString {
             if !c.is_whitespace() || self.fill() {
                 buf.set_string(x, y, c.to_string(), style);
             }
-            x += UnicodeWidthChar::width(c).unwrap() as u16;
+            x += UnicodeWidthChar::width(c).unwrap_or(0) as u16;
         }
     }
 
     /// Return true if the coord is on the shape.
     fn hit(&self, coord: Coord) -> bool;
+
+    /// SVG elements of the shape, relative to its upper-left corner.
+    /// By default, the glyphs of the shape are drawn as texts.
+    fn svg(&self) -> String {
+        let mut elements = String::new();
+        if self.fill() {
+            elements += &svg::background(self.size());
+        }
+        elements + &svg::text(&self.to_string())
+    }
+
+    /// Encode the shape as a record of the document format.
+    /// The record can be decoded by `FromStr` of the concrete shape.
+    fn encode(&self) -> String;
+
+    /// The shape in the JSON scene format.
+    fn to_scene(&self) -> SceneShape;
+
+    fn clone_box(&self) -> Box<dyn Shape>;
 }
@@ -1,5 +1,8 @@
 use super::{style::Style, Shape};
-use crate::util::*;
+use crate::{
+    app::{export::svg, scene::SceneShape},
+    util::*,
+};
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct Path {
@@ -139,6 +142,10 @@ impl Shape for Path {
         false
     }
 
+    fn modifier(&self) -> ratatui::style::Modifier {
+        self.line_style.modifier()
+    }
+
     fn hit(&self, coord: Coord) -> bool {
         let mut current = -self.start_to_upper_left;
         for &d in &self.path {
@@ -149,4 +156,101 @@ impl Shape for Path {
         }
         current == coord
     }
+
+    fn svg(&self) -> String {
+        let start = -self.start_to_upper_left;
+        let cells: Vec<Coord> = std::iter::once(start)
+            .chain(self.path.iter().scan(start, |c, &d| {
+                *c = c.adjacency(d);
+                Some(*c)
+            }))
+            .collect();
+
+        let mut elements = svg::polyline(&cells, self.line_style, false);
+        if let (true, Some(first)) = (self.has_start_arrow, self.path.first()) {
+            elements += &svg::arrow(start, first.opposite());
+        }
+        if let (true, Some(last)) = (self.has_end_arrow, self.path.last()) {
+            elements += &svg::arrow(*cells.last().unwrap(), *last);
+        }
+        elements
+    }
+
+    fn encode(&self) -> String {
+        let arrows = match (self.has_start_arrow, self.has_end_arrow) {
+            (false, false) => "-",
+            (true, false) => "<",
+            (false, true) => ">",
+            (true, true) => "<>",
+        };
+        let path: String = self
+            .path
+            .iter()
+            .map(|d| match d {
+                Direction::Left => 'h',
+                Direction::Down => 'j',
+                Direction::Up => 'k',
+                Direction::Right => 'l',
+            })
+            .collect();
+        format!("path {} {} {}", self.line_style, arrows, path)
+    }
+
+    fn to_scene(&self) -> SceneShape {
+        SceneShape::Path {
+            style: self.line_style,
+            directions: self.path.clone(),
+            start_arrow: self.has_start_arrow,
+            end_arrow: self.has_end_arrow,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+impl std::str::FromStr for Path {
+    type Err = anyhow::Error;
+
+    /// Decode `path <style> <arrows> <directions>`.
+    ///
+    /// `<arrows>` is one of `-`, `<`, `>` and `<>`.
+    /// `<directions>` is a sequence of `h`, `j`, `k` and `l`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(' ');
+        anyhow::ensure!(fields.next() == Some("path"), "not a path record");
+        let (Some(style), Some(arrows), path, None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            anyhow::bail!("malformed path record");
+        };
+
+        let (has_start_arrow, has_end_arrow) = match arrows {
+            "-" => (false, false),
+            "<" => (true, false),
+            ">" => (false, true),
+            "<>" => (true, true),
+            _ => anyhow::bail!("unknown arrows `{}`", arrows),
+        };
+
+        let path = path
+            .unwrap_or_default()
+            .chars()
+            .map(|c| match c {
+                'h' => Ok(Direction::Left),
+                'j' => Ok(Direction::Down),
+                'k' => Ok(Direction::Up),
+                'l' => Ok(Direction::Right),
+                _ => Err(anyhow::anyhow!("unknown direction `{}`", c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self::new(
+            path,
+            has_start_arrow,
+            has_end_arrow,
+            style.parse()?,
+        ))
+    }
 }
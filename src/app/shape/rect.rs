@@ -1,5 +1,8 @@
 use super::{style::Style, Shape};
-use crate::util::*;
+use crate::{
+    app::{export::svg, scene::SceneShape},
+    util::*,
+};
 
 #[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub struct Rect {
@@ -51,8 +54,60 @@ impl Shape for Rect {
         true
     }
 
+    fn modifier(&self) -> ratatui::style::Modifier {
+        self.line_style.modifier()
+    }
+
     fn hit(&self, coord: Coord) -> bool {
         (0..self.size.width as i16).contains(&coord.x)
             && (0..self.size.height as i16).contains(&coord.y)
     }
+
+    fn svg(&self) -> String {
+        let (w, h) = (self.size.width as i16 - 1, self.size.height as i16 - 1);
+        let corners = [
+            Coord::new(0, 0),
+            Coord::new(w, 0),
+            Coord::new(w, h),
+            Coord::new(0, h),
+        ];
+        svg::background(self.size) + &svg::polyline(&corners, self.line_style, true)
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "rect {} {} {}",
+            self.size.width, self.size.height, self.line_style
+        )
+    }
+
+    fn to_scene(&self) -> SceneShape {
+        SceneShape::Rect {
+            size: self.size,
+            style: self.line_style,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Shape> {
+        Box::new(*self)
+    }
+}
+
+impl std::str::FromStr for Rect {
+    type Err = anyhow::Error;
+
+    /// Decode `rect <width> <height> <style>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(' ');
+        anyhow::ensure!(fields.next() == Some("rect"), "not a rect record");
+        let (Some(width), Some(height), Some(style), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            anyhow::bail!("malformed rect record");
+        };
+
+        let size = Size::new(width.parse()?, height.parse()?);
+        anyhow::ensure!(size.width > 0 && size.height > 0, "rect must not be empty");
+        Ok(Self::new(size, style.parse()?))
+    }
 }
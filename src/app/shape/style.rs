@@ -23,6 +23,9 @@
 //
 // ╱ ╲ ╳ ╴ ╵ ╶ ╷ ╸ ╹ ╺ ╻ ╼ ╽ ╾ ╿
 
+use ratatui::style::Modifier;
+use serde::{Deserialize, Serialize};
+
 pub struct Chips {
     pub horizontal: char,
     pub vertical: char,
@@ -34,9 +37,15 @@ pub struct Chips {
     pub down_arrow: char,
     pub left_arrow: char,
     pub right_arrow: char,
+    pub top_junction: char,
+    pub bottom_junction: char,
+    pub left_junction: char,
+    pub right_junction: char,
+    pub cross: char,
 }
 
 impl Chips {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         horizontal: char,
         vertical: char,
@@ -60,11 +69,27 @@ impl Chips {
             down_arrow,
             left_arrow,
             right_arrow,
+            top_junction: ' ',
+            bottom_junction: ' ',
+            left_junction: ' ',
+            right_junction: ' ',
+            cross: ' ',
         }
     }
+
+    /// Set the junctions: `┬`, `┴`, `├`, `┤` and `┼`.
+    fn junctions(mut self, top: char, bottom: char, left: char, right: char, cross: char) -> Self {
+        self.top_junction = top;
+        self.bottom_junction = bottom;
+        self.left_junction = left;
+        self.right_junction = right;
+        self.cross = cross;
+        self
+    }
 }
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Style {
     Single,
     SingleBold,
@@ -75,15 +100,40 @@ pub enum Style {
 }
 
 impl Style {
+    pub const ALL: [Style; 6] = [
+        Style::Single,
+        Style::SingleBold,
+        Style::Double,
+        Style::Dash,
+        Style::Dot,
+        Style::Ascii,
+    ];
+
     pub fn chips(&self) -> Chips {
         use Style::*;
         match self {
-            Single => Chips::new('─', '│', '┌', '┐', '└', '┘', '▲', '▼', '◀', '▶'),
-            SingleBold => Chips::new('━', '┃', '┏', '┓', '┗', '┛', '▲', '▼', '◀', '▶'),
-            Double => Chips::new('═', '║', '╔', '╗', '╚', '╝', '▲', '▼', '◀', '▶'),
-            Dash => Chips::new('╌', '╎', '┌', '┐', '└', '┘', '▲', '▼', '◀', '▶'),
-            Dot => Chips::new('.', '.', '.', '.', '.', '.', '^', 'v', '<', '>'),
-            Ascii => Chips::new('-', '|', '+', '+', '+', '+', '^', 'v', '<', '>'),
+            Single => Chips::new('─', '│', '┌', '┐', '└', '┘', '▲', '▼', '◀', '▶')
+                .junctions('┬', '┴', '├', '┤', '┼'),
+            SingleBold => Chips::new('━', '┃', '┏', '┓', '┗', '┛', '▲', '▼', '◀', '▶')
+                .junctions('┳', '┻', '┣', '┫', '╋'),
+            Double => Chips::new('═', '║', '╔', '╗', '╚', '╝', '▲', '▼', '◀', '▶')
+                .junctions('╦', '╩', '╠', '╣', '╬'),
+            Dash => Chips::new('╌', '╎', '┌', '┐', '└', '┘', '▲', '▼', '◀', '▶')
+                .junctions('┬', '┴', '├', '┤', '┼'),
+            Dot => Chips::new('.', '.', '.', '.', '.', '.', '^', 'v', '<', '>')
+                .junctions('.', '.', '.', '.', '.'),
+            Ascii => Chips::new('-', '|', '+', '+', '+', '+', '^', 'v', '<', '>')
+                .junctions('+', '+', '+', '+', '+'),
+        }
+    }
+
+    /// Text attributes of the lines.
+    pub fn modifier(&self) -> Modifier {
+        use Style::*;
+        match self {
+            SingleBold => Modifier::BOLD,
+            Dash | Dot => Modifier::DIM,
+            Single | Double | Ascii => Modifier::empty(),
         }
     }
 
@@ -100,3 +150,35 @@ impl Style {
         }
     }
 }
+
+impl std::fmt::Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Style::*;
+        let name = match self {
+            Single => "single",
+            SingleBold => "single-bold",
+            Double => "double",
+            Dash => "dash",
+            Dot => "dot",
+            Ascii => "ascii",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for Style {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Style::*;
+        match s {
+            "single" => Ok(Single),
+            "single-bold" => Ok(SingleBold),
+            "double" => Ok(Double),
+            "dash" => Ok(Dash),
+            "dot" => Ok(Dot),
+            "ascii" => Ok(Ascii),
+            _ => anyhow::bail!("unknown line style `{}`", s),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use super::{style::Style, text, Shape};
+use crate::{
+    app::scene::SceneShape,
+    util::{Coord, Size},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Grid of cells with a line between every two rows and columns.
+///
+/// ```text
+/// ┌──────┬─────┐
+/// │ name │ age │
+/// ├──────┼─────┤
+/// │ bob  │ 42  │
+/// └──────┴─────┘
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Table {
+    /// Rows of cells. All rows have the same number of cells.
+    cells: Vec<Vec<String>>,
+    /// Width of the text of each column.
+    widths: Vec<usize>,
+    line_style: Style,
+}
+
+impl Table {
+    /// Make a table from rows of cells.
+    /// Short rows are padded with empty cells, and cells are made into single lines.
+    pub fn new(cells: Vec<Vec<String>>, line_style: Style) -> Self {
+        let num_cols = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+        let cells: Vec<Vec<String>> = cells
+            .into_iter()
+            .map(|row| {
+                let mut row: Vec<String> = row
+                    .iter()
+                    .map(|cell| text::sanitize(cell, 0).replace('\n', " "))
+                    .collect();
+                row.resize(num_cols, String::new());
+                row
+            })
+            .collect();
+
+        let widths = (0..num_cols)
+            .map(|col| {
+                cells
+                    .iter()
+                    .map(|row| UnicodeWidthStr::width(row[col].as_str()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        Self {
+            cells,
+            widths,
+            line_style,
+        }
+    }
+
+    /// Horizontal line with the junctions.
+    fn line(&self, left: char, junction: char, right: char) -> String {
+        let horizontal = self.line_style.chips().horizontal;
+        let segments: Vec<String> = self
+            .widths
+            .iter()
+            .map(|w| horizontal.to_string().repeat(w + 2))
+            .collect();
+        format!("{}{}{}", left, segments.join(&junction.to_string()), right)
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chips = self.line_style.chips();
+        let vertical = chips.vertical.to_string();
+
+        let mut lines = vec![self.line(
+            chips.upper_left_corner,
+            chips.top_junction,
+            chips.upper_right_corner,
+        )];
+        for (i, row) in self.cells.iter().enumerate() {
+            if i > 0 {
+                lines.push(self.line(chips.left_junction, chips.cross, chips.right_junction));
+            }
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&self.widths)
+                .map(|(cell, w)| {
+                    let padding = w - UnicodeWidthStr::width(cell.as_str());
+                    format!(" {}{} ", cell, " ".repeat(padding))
+                })
+                .collect();
+            lines.push(format!("{}{}{}", vertical, cells.join(&vertical), vertical));
+        }
+        lines.push(self.line(
+            chips.lower_left_corner,
+            chips.bottom_junction,
+            chips.lower_right_corner,
+        ));
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl Shape for Table {
+    fn size(&self) -> Size {
+        let width = self.widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+        let height = self.cells.len() * 2 + 1;
+        Size::new(width as u16, height as u16)
+    }
+
+    fn fill(&self) -> bool {
+        true
+    }
+
+    fn modifier(&self) -> ratatui::style::Modifier {
+        self.line_style.modifier()
+    }
+
+    fn hit(&self, coord: Coord) -> bool {
+        let size = self.size();
+        (0..size.width as i16).contains(&coord.x) && (0..size.height as i16).contains(&coord.y)
+    }
+
+    fn encode(&self) -> String {
+        let cells: Vec<String> = self
+            .cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.replace('\\', "\\\\").replace('|', "\\|"))
+            .collect();
+        format!(
+            "table {} {} {}",
+            self.line_style,
+            self.widths.len(),
+            cells.join("|")
+        )
+    }
+
+    fn to_scene(&self) -> SceneShape {
+        SceneShape::Table {
+            style: self.line_style,
+            cells: self.cells.clone(),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+impl std::str::FromStr for Table {
+    type Err = anyhow::Error;
+
+    /// Decode `table <style> <columns> <cells>`.
+    ///
+    /// `<cells>` are in row-major order and separated by `|`.
+    /// `\` and `|` in cells are escaped as `\\` and `\|`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.splitn(4, ' ');
+        anyhow::ensure!(fields.next() == Some("table"), "not a table record");
+        let (Some(style), Some(num_cols), Some(record)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            anyhow::bail!("malformed table record");
+        };
+        let num_cols: usize = num_cols.parse()?;
+        anyhow::ensure!(num_cols > 0, "table must not be empty");
+
+        let mut cells = vec![String::new()];
+        let mut chars = record.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '|' => cells.push(String::new()),
+                '\\' => match chars.next() {
+                    Some(c @ ('\\' | '|')) => cells.last_mut().unwrap().push(c),
+                    Some(c) => anyhow::bail!("unknown escape `\\{}`", c),
+                    None => anyhow::bail!("unterminated escape"),
+                },
+                c => cells.last_mut().unwrap().push(c),
+            }
+        }
+        anyhow::ensure!(
+            cells.len() % num_cols == 0,
+            "{} cells do not fit in {} columns",
+            cells.len(),
+            num_cols
+        );
+
+        let rows = cells.chunks(num_cols).map(<[String]>::to_vec).collect();
+        Ok(Self::new(rows, style.parse()?))
+    }
+}
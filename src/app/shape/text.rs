@@ -1,6 +1,7 @@
 use super::Shape;
+use crate::app::scene::SceneShape;
 use crate::util::{Coord, Size};
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct Text {
@@ -13,6 +14,34 @@ impl Text {
     }
 }
 
+const TAB_WIDTH: usize = 8;
+
+/// Make a string, e.g. pasted one, drawable as a text.
+/// Line breaks are normalized to `\n`, tabs are expanded to spaces, and other control chars
+/// are dropped. `column` is the width of the line before the string.
+pub fn sanitize(s: &str, mut column: usize) -> String {
+    let mut sanitized = String::new();
+    for c in s.replace("\r\n", "\n").replace('\r', "\n").chars() {
+        match c {
+            '\n' => {
+                sanitized.push('\n');
+                column = 0;
+            }
+            '\t' => {
+                let width = TAB_WIDTH - column % TAB_WIDTH;
+                sanitized.push_str(&" ".repeat(width));
+                column += width;
+            }
+            c if c.is_control() => {}
+            c => {
+                sanitized.push(c);
+                column += UnicodeWidthChar::width(c).unwrap_or(0);
+            }
+        }
+    }
+    sanitized
+}
+
 impl std::fmt::Display for Text {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.s)
@@ -47,4 +76,74 @@ impl Shape for Text {
 
         false
     }
+
+    fn encode(&self) -> String {
+        let mut record = "text ".to_string();
+        for c in self.s.chars() {
+            match c {
+                '\\' => record.push_str("\\\\"),
+                '\n' => record.push_str("\\n"),
+                '\r' => record.push_str("\\r"),
+                c => record.push(c),
+            }
+        }
+        record
+    }
+
+    fn to_scene(&self) -> SceneShape {
+        SceneShape::Text {
+            text: self.s.clone(),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+impl std::str::FromStr for Text {
+    type Err = anyhow::Error;
+
+    /// Decode `text <escaped string>`.
+    /// Only `\\`, `\n` and `\r` are escaped so that a record fits in a line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(escaped) = s.strip_prefix("text ") else {
+            anyhow::bail!("not a text record");
+        };
+
+        let mut text = String::new();
+        let mut chars = escaped.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('\\') => text.push('\\'),
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some(c) => anyhow::bail!("unknown escape `\\{}`", c),
+                None => anyhow::bail!("unterminated escape"),
+            }
+        }
+        Ok(Self::new(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_pasted_string() {
+        assert_eq!(sanitize("a\r\nb\rc\n", 0), "a\nb\nc\n");
+        assert_eq!(sanitize("a\x1b[1mb\x07", 0), "a[1mb");
+    }
+
+    #[test]
+    fn expand_tabs_from_column() {
+        assert_eq!(sanitize("a\tb", 0), "a       b");
+        assert_eq!(sanitize("\tb", 3), "     b");
+        assert_eq!(sanitize("あ\tb\n\tc", 0), "あ      b\n        c");
+    }
 }
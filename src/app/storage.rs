@@ -0,0 +1,178 @@
+//! Places where a document is stored.
+
+mod comment;
+mod markdown;
+
+use super::{canvas::Canvas, document, import, shape::Shape};
+use crate::util::Coord;
+use anyhow::Context;
+use markdown::Selector;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum Storage {
+    /// Native document file.
+    File(PathBuf),
+    /// Fenced code block in a Markdown file.
+    Markdown(PathBuf, Selector),
+    /// Comment lines in a source file, with their prefix once it is detected.
+    Comment(PathBuf, Range<usize>, Option<comment::Prefix>),
+    /// Stdin to load, and the rendered canvas to write to stdout on exit.
+    /// Stdin is kept once it is read, since it can be read only once.
+    Pipe {
+        input: Option<String>,
+        output: Option<String>,
+    },
+}
+
+impl Storage {
+    /// Path of the file containing the document, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Storage::File(path) | Storage::Markdown(path, _) | Storage::Comment(path, ..) => {
+                Some(path)
+            }
+            Storage::Pipe { .. } => None,
+        }
+    }
+
+    /// Return true if the file of the storage exists.
+    pub fn exists(&self) -> bool {
+        match self {
+            Storage::File(path) | Storage::Markdown(path, _) | Storage::Comment(path, ..) => {
+                path.exists()
+            }
+            Storage::Pipe { .. } => true,
+        }
+    }
+
+    /// Last modification time of the file of the storage, if any.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.path()?.metadata().ok()?.modified().ok()
+    }
+
+    /// Load shapes ordered from back to front.
+    pub fn load(&mut self) -> anyhow::Result<Vec<(Coord, Box<dyn Shape>)>> {
+        let storage = self.to_string();
+        match self {
+            Storage::File(path) => {
+                let doc = std::fs::read_to_string(path)?;
+                document::decode(&doc)
+            }
+            Storage::Markdown(path, selector) => markdown::load(path, selector),
+            Storage::Comment(path, range, prefix) => comment::load(path, range, prefix),
+            Storage::Pipe { input, .. } => {
+                let text = match input {
+                    Some(text) => text,
+                    None => input.insert(std::io::read_to_string(std::io::stdin())?),
+                };
+                // Accept both a native document and a plain text.
+                document::decode(text).or_else(|_| Ok(import::diagram(text)))
+            }
+        }
+        .with_context(|| format!("failed to load {}", storage))
+    }
+
+    /// Save the canvas.
+    /// The location of the storage may move by the saved content.
+    pub fn save(&mut self, canvas: &Canvas) -> anyhow::Result<()> {
+        let storage = self.to_string();
+        match self {
+            Storage::File(path) => {
+                let doc = document::encode(canvas.shapes());
+                Ok(std::fs::write(path, doc)?)
+            }
+            Storage::Markdown(path, selector) => markdown::save(path, selector, canvas),
+            Storage::Comment(path, range, prefix) => comment::save(path, range, prefix, canvas),
+            Storage::Pipe { output, .. } => {
+                *output = Some(canvas.to_plain_text());
+                Ok(())
+            }
+        }
+        .with_context(|| format!("failed to save {}", storage))
+    }
+}
+
+impl std::str::FromStr for Storage {
+    type Err = anyhow::Error;
+
+    /// Parse the location given by user.
+    ///
+    /// - `<file>.md#<n>` : `n`-th (1-origin) fenced code block in the Markdown file.
+    /// - `<file>.md#<name>` : fenced code block marked with `<!-- uart:<name>`.
+    /// - `<file>:<start>-<end>` : comment lines (1-origin, inclusive) in the source file.
+    /// - `-` : stdin and stdout.
+    /// - `<file>` : native document file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            return Ok(Storage::Pipe {
+                input: None,
+                output: None,
+            });
+        }
+
+        if let Some((path, lines)) = s.rsplit_once(':') {
+            if let Some(range) = comment::parse_lines(lines) {
+                return Ok(Storage::Comment(PathBuf::from(path), range?, None));
+            }
+        }
+
+        let (path, selector) = match s.rsplit_once('#') {
+            Some((path, selector)) => (PathBuf::from(path), Some(selector)),
+            None => (PathBuf::from(s), None),
+        };
+
+        let is_markdown = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("md" | "markdown")
+        );
+
+        match (is_markdown, selector) {
+            (true, Some(selector)) => Ok(Storage::Markdown(path, selector.parse()?)),
+            (true, None) => anyhow::bail!("select a code block by `{}#<index or name>`", s),
+            (false, _) => Ok(Storage::File(PathBuf::from(s))),
+        }
+    }
+}
+
+impl std::fmt::Display for Storage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Storage::File(path) => write!(f, "{}", path.display()),
+            Storage::Markdown(path, selector) => write!(f, "{}#{}", path.display(), selector),
+            Storage::Comment(path, range, _) => {
+                write!(f, "{}:{}-{}", path.display(), range.start + 1, range.end)
+            }
+            Storage::Pipe { .. } => write!(f, "-"),
+        }
+    }
+}
+
+/// Replace the lines of the file in `range`.
+/// The line ending of the file is kept.
+fn replace_lines(path: &Path, range: Range<usize>, replacement: Vec<String>) -> anyhow::Result<()> {
+    let file = std::fs::read_to_string(path)?;
+    let newline = if file.contains("\r\n") { "\r\n" } else { "\n" };
+    let lines: Vec<&str> = file.lines().collect();
+    anyhow::ensure!(
+        range.end <= lines.len(),
+        "line {} is out of the file",
+        range.end
+    );
+
+    let mut new_file = lines[..range.start]
+        .iter()
+        .map(|l| l.to_string())
+        .chain(replacement)
+        .chain(lines[range.end..].iter().map(|l| l.to_string()))
+        .collect::<Vec<_>>()
+        .join(newline);
+    if file.ends_with('\n') {
+        new_file += newline;
+    }
+    Ok(std::fs::write(path, new_file)?)
+}
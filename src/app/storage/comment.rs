@@ -0,0 +1,221 @@
+//! Diagram in comments of a source file.
+//!
+//! ```text
+//!     /// ┌───┐
+//!     /// │ a │
+//!     /// └───┘
+//! ```
+//!
+//! The common prefix of the lines, that is the indent, the comment marker and the spaces
+//! after the marker, is stripped on load and added again on save.
+
+use crate::{
+    app::{canvas::Canvas, import, shape::Shape},
+    util::Coord,
+};
+use std::{ops::Range, path::Path};
+
+/// Comment markers, longer one first.
+const MARKERS: [&str; 4] = ["///", "//!", "//", "#"];
+
+/// Common prefix of comment lines.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Prefix {
+    indent: String,
+    marker: &'static str,
+    /// Number of spaces after the marker.
+    padding: usize,
+}
+
+impl Prefix {
+    fn detect(lines: &[&str]) -> Self {
+        let lines: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+
+        let indent = lines
+            .iter()
+            .map(|l| &l[..l.len() - l.trim_start().len()])
+            .reduce(|a, b| {
+                let len = a
+                    .chars()
+                    .zip(b.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(c, _)| c.len_utf8())
+                    .sum();
+                &a[..len]
+            })
+            .unwrap_or_default()
+            .to_string();
+
+        let bodies = lines.iter().map(|l| &l[indent.len()..]);
+        let marker = MARKERS
+            .into_iter()
+            .find(|m| !lines.is_empty() && bodies.clone().all(|b| b.starts_with(m)))
+            .unwrap_or_default();
+
+        let padding = bodies
+            .map(|b| &b[marker.len()..])
+            .filter(|b| !b.trim().is_empty())
+            .map(|b| b.len() - b.trim_start_matches(' ').len())
+            .min()
+            .unwrap_or(if marker.is_empty() { 0 } else { 1 });
+
+        Self {
+            indent,
+            marker,
+            padding,
+        }
+    }
+
+    fn strip<'a>(&self, line: &'a str) -> &'a str {
+        if line.trim().is_empty() {
+            return "";
+        }
+        let body = &line[self.indent.len() + self.marker.len()..];
+        if body.trim().is_empty() {
+            ""
+        } else {
+            &body[self.padding..]
+        }
+    }
+
+    fn add(&self, line: &str) -> String {
+        if line.is_empty() {
+            format!("{}{}", self.indent, self.marker)
+                .trim_end()
+                .to_string()
+        } else {
+            format!(
+                "{}{}{}{}",
+                self.indent,
+                self.marker,
+                " ".repeat(self.padding),
+                line
+            )
+        }
+    }
+}
+
+/// Parse 1-origin inclusive line range `<start>-<end>` or `<line>`.
+/// Return `None` if `s` does not look like a line range.
+pub fn parse_lines(s: &str) -> Option<anyhow::Result<Range<usize>>> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return None;
+    }
+
+    let parse = || -> anyhow::Result<Range<usize>> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let (start, end): (usize, usize) = (start.parse()?, end.parse()?);
+        anyhow::ensure!(1 <= start && start <= end, "invalid line range `{}`", s);
+        Ok(start - 1..end)
+    };
+    Some(parse())
+}
+
+fn read_lines(file: &str, range: &Range<usize>) -> anyhow::Result<Vec<String>> {
+    let lines: Vec<&str> = file.lines().collect();
+    anyhow::ensure!(
+        range.end <= lines.len(),
+        "the file has only {} lines",
+        lines.len()
+    );
+    Ok(lines[range.clone()].iter().map(|l| l.to_string()).collect())
+}
+
+/// Load the comment lines as shapes. `prefix` is set to the detected prefix.
+pub fn load(
+    path: &Path,
+    range: &Range<usize>,
+    prefix: &mut Option<Prefix>,
+) -> anyhow::Result<Vec<(Coord, Box<dyn Shape>)>> {
+    let file = std::fs::read_to_string(path)?;
+    let lines = read_lines(&file, range)?;
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+
+    let prefix = prefix.insert(Prefix::detect(&lines));
+    let text = lines
+        .iter()
+        .fold(String::new(), |acc, l| acc + prefix.strip(l) + "\n");
+    Ok(import::diagram(&text))
+}
+
+/// Write the rendered canvas back into the comment lines.
+/// `range` is updated to the lines written.
+///
+/// The prefix detected on load is used, or detected from the lines if the lines were not
+/// loaded. An empty canvas is written as an empty comment line to keep the place.
+pub fn save(
+    path: &Path,
+    range: &mut Range<usize>,
+    prefix: &mut Option<Prefix>,
+    canvas: &Canvas,
+) -> anyhow::Result<()> {
+    let file = std::fs::read_to_string(path)?;
+    let lines = read_lines(&file, range)?;
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+
+    let prefix = prefix.get_or_insert_with(|| Prefix::detect(&lines));
+    let mut replacement: Vec<String> = canvas
+        .to_plain_text_from_origin()
+        .lines()
+        .map(|l| prefix.add(l))
+        .collect();
+    if replacement.is_empty() {
+        replacement.push(prefix.add(""));
+    }
+
+    let new_range = range.start..range.start + replacement.len();
+    super::replace_lines(path, range.clone(), replacement)?;
+    *range = new_range;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line_ranges() {
+        assert_eq!(parse_lines("3").unwrap().unwrap(), 2..3);
+        assert_eq!(parse_lines("2-5").unwrap().unwrap(), 1..5);
+        assert!(parse_lines("5-2").unwrap().is_err());
+        assert!(parse_lines("0").unwrap().is_err());
+        assert!(parse_lines("doc.md").is_none());
+        assert!(parse_lines("").is_none());
+    }
+
+    #[test]
+    fn detect_prefix() {
+        let lines = ["    /// ┌─┐", "    ///", "    ///   x", "", "    /// └─┘"];
+        let prefix = Prefix::detect(&lines);
+        assert_eq!(prefix.indent, "    ");
+        assert_eq!(prefix.marker, "///");
+        assert_eq!(prefix.padding, 1);
+
+        let stripped: Vec<&str> = lines.iter().map(|l| prefix.strip(l)).collect();
+        assert_eq!(stripped, ["┌─┐", "", "  x", "", "└─┘"]);
+        assert_eq!(prefix.add("┌─┐"), "    /// ┌─┐");
+        assert_eq!(prefix.add(""), "    ///");
+    }
+
+    #[test]
+    fn detect_prefix_of_mixed_lines() {
+        // `//` is common to `///` and `//`, and the rest of `///` is kept.
+        let prefix = Prefix::detect(&["/// a", "//   b"]);
+        assert_eq!(prefix.marker, "//");
+        assert_eq!(prefix.strip("/// a"), "/ a");
+        assert_eq!(prefix.strip("//   b"), "   b");
+
+        let prefix = Prefix::detect(&["  # a", "  #   b"]);
+        assert_eq!(prefix.marker, "#");
+        assert_eq!(prefix.strip("  #   b"), "  b");
+
+        let prefix = Prefix::detect(&["# a", "  b"]);
+        assert_eq!(prefix.indent, "");
+        assert_eq!(prefix.marker, "");
+        assert_eq!(prefix.padding, 0);
+    }
+}
@@ -0,0 +1,335 @@
+//! Diagram embedded in a fenced code block of a Markdown file.
+//!
+//! ````markdown
+//! <!-- uart:name
+//! uart 1
+//! 0 0 rect 4 3 single
+//! -->
+//! ```
+//! ┌──┐
+//! │  │
+//! └──┘
+//! ```
+//! ````
+//!
+//! The code block holds the rendered canvas, and the hidden comment just before the block
+//! holds the native document so that shapes can be restored.
+//! `&` and `>` in the document are escaped as `&amp;` and `&gt;` not to close the comment.
+//! A code block without the comment is imported by `import::diagram`.
+
+use crate::{
+    app::{canvas::Canvas, document, import, shape::Shape},
+    util::Coord,
+};
+use anyhow::Context;
+use std::{ops::Range, path::Path};
+
+const MARKER: &str = "<!-- uart";
+const COMMENT_END: &str = "-->";
+
+/// Selector of a fenced code block.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum Selector {
+    /// 1-origin index of the block in the file.
+    Index(usize),
+    /// Name given by `<!-- uart:<name>` marker.
+    Name(String),
+}
+
+impl std::str::FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            anyhow::bail!("empty code block selector");
+        }
+        match s.parse::<usize>() {
+            Ok(0) => anyhow::bail!("code block index starts from 1"),
+            Ok(i) => Ok(Selector::Index(i)),
+            Err(_) => Ok(Selector::Name(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selector::Index(i) => write!(f, "{}", i),
+            Selector::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Fenced code block.
+struct Block {
+    /// Name given by the marker.
+    name: Option<String>,
+    /// Lines of the marker comment just before the block.
+    meta: Option<Range<usize>>,
+    /// Lines from the opening fence to the closing fence.
+    fence: Range<usize>,
+    /// Indent of the opening fence.
+    indent: usize,
+}
+
+impl Block {
+    /// Lines of the block including the marker comment.
+    fn lines(&self) -> Range<usize> {
+        self.meta.as_ref().map_or(self.fence.start, |m| m.start)..self.fence.end
+    }
+
+    /// Document in the marker comment.
+    fn document(&self, lines: &[&str]) -> Option<String> {
+        let meta = self.meta.as_ref()?;
+        if meta.len() < 2 {
+            return None;
+        }
+        let doc = lines[meta.start + 1..meta.end - 1]
+            .iter()
+            .map(|l| self.dedent(l))
+            .fold(String::new(), |acc, l| acc + l + "\n");
+        Some(unescape(&doc))
+    }
+
+    /// Content of the code block.
+    fn content(&self, lines: &[&str]) -> String {
+        lines[self.fence.start + 1..self.fence.end - 1]
+            .iter()
+            .map(|l| self.dedent(l))
+            .fold(String::new(), |acc, l| acc + l + "\n")
+    }
+
+    /// Strip the indent of the opening fence from the line.
+    fn dedent<'a>(&self, line: &'a str) -> &'a str {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        &line[indent.min(self.indent)..]
+    }
+}
+
+/// Parse `<!-- uart[:<name>]` and return the name.
+fn parse_marker(line: &str) -> Option<Option<String>> {
+    let rest = line.trim().strip_prefix(MARKER)?;
+    let rest = rest.strip_suffix(COMMENT_END).unwrap_or(rest).trim_end();
+    if rest.is_empty() {
+        return Some(None);
+    }
+    let name = rest.strip_prefix(':')?.trim();
+    Some((!name.is_empty()).then(|| name.to_string()))
+}
+
+/// Parse an opening fence and return (indent, fence char, fence length).
+fn parse_fence(line: &str) -> Option<(usize, char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    let indent = line.len() - trimmed.len();
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|x| *x == c).count();
+    if indent > 3 || len < 3 || (c == '`' && trimmed[len..].contains('`')) {
+        return None;
+    }
+    Some((indent, c, len))
+}
+
+fn is_closing_fence(line: &str, c: char, len: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= len && trimmed.chars().all(|x| x == c)
+}
+
+/// Find all fenced code blocks.
+fn blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = vec![];
+    // (name, lines) of the last marker comment.
+    let mut marker: Option<(Option<String>, Range<usize>)> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        if let Some(name) = parse_marker(lines[i]) {
+            let end = (i..lines.len()).find(|&j| lines[j].contains(COMMENT_END));
+            if let Some(end) = end {
+                marker = Some((name, i..end + 1));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if let Some((indent, c, len)) = parse_fence(lines[i]) {
+            let close = (i + 1..lines.len()).find(|&j| is_closing_fence(lines[j], c, len));
+            if let Some(close) = close {
+                let (name, meta) = match marker.take() {
+                    Some((name, meta)) if meta.end == i => (name, Some(meta)),
+                    _ => (None, None),
+                };
+                blocks.push(Block {
+                    name,
+                    meta,
+                    fence: i..close + 1,
+                    indent,
+                });
+                i = close + 1;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    blocks
+}
+
+fn select<'a>(blocks: &'a [Block], selector: &Selector) -> anyhow::Result<&'a Block> {
+    match selector {
+        Selector::Index(i) => blocks.get(i - 1),
+        Selector::Name(name) => blocks.iter().find(|b| b.name.as_ref() == Some(name)),
+    }
+    .with_context(|| format!("code block `{}` is not found", selector))
+}
+
+fn escape(doc: &str) -> String {
+    doc.replace('&', "&amp;").replace('>', "&gt;")
+}
+
+fn unescape(doc: &str) -> String {
+    doc.replace("&gt;", ">").replace("&amp;", "&")
+}
+
+/// Load shapes from the code block.
+pub fn load(path: &Path, selector: &Selector) -> anyhow::Result<Vec<(Coord, Box<dyn Shape>)>> {
+    let file = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = file.lines().collect();
+    let blocks = blocks(&lines);
+    let block = select(&blocks, selector)?;
+
+    if let Some(doc) = block.document(&lines) {
+        return document::decode(&doc);
+    }
+
+    Ok(import::diagram(&block.content(&lines)))
+}
+
+/// Write the rendered canvas back into the code block.
+/// The rest of the file is not changed.
+pub fn save(path: &Path, selector: &Selector, canvas: &Canvas) -> anyhow::Result<()> {
+    let file = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = file.lines().collect();
+    let blocks = blocks(&lines);
+    let block = select(&blocks, selector)?;
+
+    let marker = match &block.name {
+        Some(name) => format!("{}:{}", MARKER, name),
+        None => MARKER.to_string(),
+    };
+    let doc = escape(&document::encode(canvas.shapes()));
+    let indent = " ".repeat(block.indent);
+    let rendered = canvas.to_plain_text_from_origin();
+    let rendered = rendered.lines().map(|l| match l {
+        "" => String::new(),
+        l => format!("{}{}", indent, l),
+    });
+
+    // The comment is indented as the block, not to break e.g. a list item containing it.
+    let comment = std::iter::once(marker.as_str())
+        .chain(doc.lines())
+        .chain([COMMENT_END])
+        .map(|l| format!("{}{}", indent, l));
+
+    let replacement: Vec<String> = comment
+        .chain([lines[block.fence.start].to_string()])
+        .chain(rendered)
+        .chain([lines[block.fence.end - 1].to_string()])
+        .collect();
+
+    super::replace_lines(path, block.lines(), replacement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_markers() {
+        assert_eq!(parse_marker("<!-- uart"), Some(None));
+        assert_eq!(
+            parse_marker(" <!-- uart:fig -->"),
+            Some(Some("fig".to_string()))
+        );
+        assert_eq!(parse_marker("<!-- uartx"), None);
+        assert_eq!(parse_marker("<!-- other -->"), None);
+    }
+
+    #[test]
+    fn parse_fences() {
+        assert_eq!(parse_fence("```"), Some((0, '`', 3)));
+        assert_eq!(parse_fence("  ~~~~text"), Some((2, '~', 4)));
+        assert_eq!(parse_fence("``"), None);
+        assert_eq!(parse_fence("    ```"), None);
+        assert_eq!(parse_fence("``` a`b"), None);
+        assert!(is_closing_fence("`````", '`', 4));
+        assert!(!is_closing_fence("```", '`', 4));
+        assert!(!is_closing_fence("~~~", '`', 3));
+    }
+
+    #[test]
+    fn find_blocks() {
+        let src = "\
+text
+```
+a
+```
+<!-- uart:fig
+uart 1
+0 0 text a&amp;b&gt;c
+-->
+````
+```
+````
+  ~~~
+  b
+  ~~~
+";
+        let lines: Vec<&str> = src.lines().collect();
+        let blocks = blocks(&lines);
+        assert_eq!(blocks.len(), 3);
+
+        assert_eq!(blocks[0].name, None);
+        assert_eq!(blocks[0].fence, 1..4);
+        assert_eq!(blocks[0].content(&lines), "a\n");
+
+        let fig = select(&blocks, &Selector::Name("fig".to_string())).unwrap();
+        assert_eq!(fig.lines(), 4..11);
+        assert_eq!(fig.content(&lines), "```\n");
+        assert_eq!(fig.document(&lines).unwrap(), "uart 1\n0 0 text a&b>c\n");
+
+        let indented = select(&blocks, &Selector::Index(3)).unwrap();
+        assert_eq!(indented.indent, 2);
+        assert_eq!(indented.content(&lines), "b\n");
+        assert!(select(&blocks, &Selector::Index(4)).is_err());
+    }
+
+    #[test]
+    fn save_indented_block() {
+        let path = std::env::temp_dir().join(format!("uart-markdown-{}.md", std::process::id()));
+        std::fs::write(&path, "- item\n\n  ```\n  a\n  ```\n- next\n").unwrap();
+        let canvas: Canvas = document::decode("uart 1\n0 0 text b\n")
+            .unwrap()
+            .into_iter()
+            .collect();
+
+        let selector = Selector::Index(1);
+        save(&path, &selector, &canvas).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let loaded = load(&path, &selector).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            saved,
+            "- item\n\n  <!-- uart\n  uart 1\n  0 0 text b\n  -->\n  ```\n  b\n  ```\n- next\n"
+        );
+        assert_eq!(document::encode(loaded.iter()), "uart 1\n0 0 text b\n");
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let doc = "0 0 text -->&gt;\n";
+        assert!(!escape(doc).contains(COMMENT_END));
+        assert_eq!(unescape(&escape(doc)), doc);
+    }
+}
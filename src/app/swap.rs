@@ -0,0 +1,85 @@
+//! Swap files keeping unsaved changes of a document, like the ones of vim.
+//!
+//! The swap file of `dir/doc.uart` is `dir/.doc.uart.swp`, containing the canvas in the native
+//! document format. For a part of a file, the suffix of the location is kept in the name,
+//! e.g. `.doc.md#fig.swp`, so that parts of the same file do not share a swap file.
+//!
+//! While a swap file is left by another session, an alternate name is used as vim does, i.e.
+//! `.swo`, `.swn` and so on down to `.swa`.
+
+use super::{canvas::Canvas, document, storage::Storage};
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Paths of the swap files for the storage in order of preference,
+/// or an empty list if the storage is not a file.
+fn paths(storage: &Storage) -> Vec<PathBuf> {
+    let Some(path) = storage.path() else {
+        return vec![];
+    };
+    let Some(file_name) = path.file_name() else {
+        return vec![];
+    };
+    let location = storage.to_string();
+    let suffix = location
+        .strip_prefix(&path.display().to_string())
+        .unwrap_or_default();
+    ('a'..='p')
+        .rev()
+        .map(|c| {
+            let name = format!(".{}{}.sw{}", file_name.to_string_lossy(), suffix, c);
+            path.with_file_name(name)
+        })
+        .collect()
+}
+
+/// Path of an existing swap file for the storage, left by another session or a crash.
+pub fn find(storage: &Storage) -> Option<PathBuf> {
+    paths(storage).into_iter().find(|path| path.exists())
+}
+
+/// Path of a new swap file for the storage, which does not overwrite existing ones.
+pub fn new_path(storage: &Storage) -> Option<PathBuf> {
+    paths(storage).into_iter().find(|path| !path.exists())
+}
+
+pub fn write(path: &Path, canvas: &Canvas) -> anyhow::Result<()> {
+    std::fs::write(path, document::encode(canvas.shapes()))
+        .with_context(|| format!("failed to write swap file {}", path.display()))
+}
+
+pub fn read(path: &Path) -> anyhow::Result<Canvas> {
+    let doc = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read swap file {}", path.display()))?;
+    let shapes =
+        document::decode(&doc).with_context(|| format!("failed to recover {}", path.display()))?;
+    Ok(shapes.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_paths_in_order() {
+        let paths = paths(&Storage::File(PathBuf::from("dir/doc.uart")));
+        assert_eq!(paths.len(), 16);
+        assert_eq!(paths[0], Path::new("dir/.doc.uart.swp"));
+        assert_eq!(paths[1], Path::new("dir/.doc.uart.swo"));
+        assert_eq!(paths[15], Path::new("dir/.doc.uart.swa"));
+    }
+
+    #[test]
+    fn swap_paths_of_parts() {
+        let markdown: Storage = "doc.md#fig".parse().unwrap();
+        assert_eq!(paths(&markdown)[0], Path::new(".doc.md#fig.swp"));
+        let comment: Storage = "src/a.rs:3-5".parse().unwrap();
+        assert_eq!(paths(&comment)[0], Path::new("src/.a.rs:3-5.swp"));
+
+        let pipe = Storage::Pipe {
+            input: None,
+            output: None,
+        };
+        assert!(paths(&pipe).is_empty());
+    }
+}
@@ -1,7 +1,79 @@
+use anyhow::Context;
 use app::App;
+use std::path::PathBuf;
 mod app;
 mod util;
 
-fn main() {
-    App::new().run();
+/// `uart [<location>] [--import <file>]...`
+///
+/// With `-` as the location, the document is read from stdin and written to stdout on `:wq`.
+fn edit(args: &[String]) -> anyhow::Result<()> {
+    let mut location = None;
+    let mut imports = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--import" => {
+                let Some(path) = args.next() else {
+                    anyhow::bail!("--import requires a file");
+                };
+                imports.push(path);
+            }
+            _ => location = Some(arg),
+        }
+    }
+
+    let mut app = App::new();
+    if let Some(location) = location {
+        app.open(location)?;
+    }
+    for path in imports {
+        app.import(path.as_ref(), None)?;
+    }
+    app.run()
+}
+
+/// `uart render <location> [--format txt|svg|html|html-css|ansi|json] [-o <file>] [--check]`
+///
+/// Write the exported document to the file (or stdout).
+/// With `--check`, fail if the file is not up to date instead of writing it.
+fn render(args: &[String]) -> anyhow::Result<()> {
+    let mut location = None;
+    let mut format = None;
+    let mut output = None;
+    let mut check = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = Some(args.next().context("--format requires a format")?),
+            "-o" => output = Some(PathBuf::from(args.next().context("-o requires a file")?)),
+            "--check" => check = true,
+            _ => location = Some(arg),
+        }
+    }
+
+    let location = location.context("no document to render")?;
+    let rendered = app::render(location, format.map(String::as_str), output.as_deref())?;
+
+    match (output, check) {
+        (Some(output), true) => {
+            let current = std::fs::read_to_string(&output).unwrap_or_default();
+            anyhow::ensure!(current == rendered, "{} is stale", output.display());
+        }
+        (Some(output), false) => std::fs::write(&output, rendered)
+            .with_context(|| format!("failed to write {}", output.display()))?,
+        (None, true) => anyhow::bail!("--check requires -o"),
+        (None, false) => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("render") => render(&args[1..]),
+        _ => edit(&args),
+    }
 }
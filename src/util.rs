@@ -9,5 +9,6 @@ pub use coord::*;
 pub use direction::*;
 pub use ext::*;
 pub use id::*;
+#[allow(unused)]
 pub use onetime_widget::*;
 pub use size::*;
@@ -1,7 +1,8 @@
 use super::Direction;
+use serde::{Deserialize, Serialize};
 
 /// Signed 2-dim coord.
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Coord {
     pub x: i16,
     pub y: i16,
@@ -1,4 +1,7 @@
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     Up,
     Left,
@@ -1,5 +1,6 @@
+use ratatui::buffer::Buffer;
 use std::ops::Range;
-use unicode_width::UnicodeWidthChar;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 pub trait StrExt {
     fn slice_by_width(&self, range: &Range<usize>) -> String;
@@ -18,7 +19,7 @@ impl<T: AsRef<str>> StrExt for T {
             .scan(usize::MAX, |width, c| {
                 // width : offset of the end of the char (0-origin).
                 //    abあc -> (0, a), (1, b), (3, あ), (4, c)
-                let delta = UnicodeWidthChar::width(c).unwrap();
+                let delta = UnicodeWidthChar::width(c).unwrap_or(0);
                 *width = width.wrapping_add(delta);
 
                 // Replace a full-width (全角) char at the edge of the screen with a space.
@@ -45,3 +46,33 @@ pub trait IterExt: Iterator {
     }
 }
 impl<I: Iterator> IterExt for I {}
+
+pub trait BufferExt {
+    fn row_symbols(&self, y: u16) -> Vec<(u16, &str)>;
+}
+
+impl BufferExt for Buffer {
+    /// Visible symbols in the row `y` with their x.
+    /// The cells hidden by wide chars are skipped.
+    fn row_symbols(&self, y: u16) -> Vec<(u16, &str)> {
+        let area = self.area;
+        let mut symbols = vec![];
+        let mut x = area.left();
+        while x < area.right() {
+            let symbol = self[(x, y)].symbol();
+            let width = UnicodeWidthStr::width(symbol).max(1) as u16;
+
+            // A wide char is broken if its trailing half is overwritten by another shape.
+            let broken =
+                (x + 1..x + width).any(|x| x >= area.right() || self[(x, y)].symbol() != " ");
+            if broken {
+                symbols.push((x, " "));
+                x += 1;
+            } else {
+                symbols.push((x, symbol));
+                x += width;
+            }
+        }
+        symbols
+    }
+}
@@ -127,7 +127,7 @@ impl<Tag> IdSet<Tag> {
     }
 
     #[inline(always)]
-    pub fn iter(&self) -> IdSetIterator<Tag> {
+    pub fn iter(&self) -> IdSetIterator<'_, Tag> {
         IdSetIterator::new(self)
     }
 
@@ -138,6 +138,16 @@ impl<Tag> IdSet<Tag> {
     }
 }
 
+impl<Tag> FromIterator<Id<Tag>> for IdSet<Tag> {
+    fn from_iter<T: IntoIterator<Item = Id<Tag>>>(iter: T) -> Self {
+        let mut set = Self::default();
+        iter.into_iter().for_each(|id| {
+            set.insert(&id);
+        });
+        set
+    }
+}
+
 pub struct IdSetIterator<'a, Tag> {
     bitset: &'a IdSet<Tag>,
     block: u32,
@@ -173,3 +183,9 @@ impl<Tag> Iterator for IdSetIterator<'_, Tag> {
         Some(Id::<Tag>::new(id))
     }
 }
+
+impl<Tag> std::fmt::Display for Id<Tag> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
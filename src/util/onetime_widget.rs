@@ -1,5 +1,6 @@
 use ratatui::widgets::Widget;
 
+#[allow(unused)]
 pub struct OnetimeWidget<F>
 where
     F: FnOnce(ratatui::layout::Rect, &mut ratatui::buffer::Buffer),
@@ -7,6 +8,7 @@ where
     render: F,
 }
 
+#[allow(unused)]
 impl<F> OnetimeWidget<F>
 where
     F: FnOnce(ratatui::layout::Rect, &mut ratatui::buffer::Buffer),
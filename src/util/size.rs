@@ -1,4 +1,6 @@
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Size {
     pub width: u16,
     pub height: u16,